@@ -0,0 +1,238 @@
+use crate::sudoku::{CellValue, Sudoku};
+
+// Storage for the numbers a cell could still hold
+pub trait CandidateSet: Clone {
+    fn full(max: u32) -> Self;
+    fn remove(&mut self, num: u32);
+    fn len(&self) -> usize;
+    // Always in ascending order
+    fn nums(&self) -> Vec<u32>;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl CandidateSet for Vec<u32> {
+    fn full(max: u32) -> Self {
+        (1..=max).collect()
+    }
+
+    fn remove(&mut self, num: u32) {
+        if let Some(index) = self.iter().position(|n| *n == num) {
+            Vec::remove(self, index);
+        }
+    }
+
+    fn len(&self) -> usize {
+        Vec::len(self)
+    }
+
+    fn nums(&self) -> Vec<u32> {
+        self.clone()
+    }
+}
+
+// Bit `n` is set when `n` fits, wide enough for every board size the text formats can write
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bitmask(u64);
+
+impl CandidateSet for Bitmask {
+    fn full(max: u32) -> Self {
+        Bitmask(((1 << max) - 1) << 1)
+    }
+
+    fn remove(&mut self, num: u32) {
+        self.0 &= !(1 << num);
+    }
+
+    fn len(&self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    fn nums(&self) -> Vec<u32> {
+        (1..u64::BITS).filter(|num| self.0 & (1 << num) != 0).collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CandidateRepr {
+    Vec,
+    Bitmask,
+}
+
+impl CandidateRepr {
+    pub fn solve(self, board: &Sudoku) -> Option<Sudoku> {
+        match self {
+            CandidateRepr::Vec => solve_with::<Vec<u32>>(board),
+            CandidateRepr::Bitmask => solve_with::<Bitmask>(board),
+        }
+    }
+}
+
+// Deterministic backtracking that always branches on the cell with the fewest candidates,
+// trying its candidates in ascending order, so every representation finds the same solution
+pub fn solve_with<C: CandidateSet>(board: &Sudoku) -> Option<Sudoku> {
+    let mut solution = None;
+    if board.conflict_count() > 0 {
+        return solution;
+    }
+
+    Search::new(board).run::<C>(&mut |values| {
+        solution = Some(board.with_values(values));
+        false
+    });
+    solution
+}
+
+// Up to `limit` solutions, in the order the search finds them
+pub fn solutions_with<C: CandidateSet>(board: &Sudoku, limit: usize) -> Vec<Sudoku> {
+    let mut solutions = vec![];
+    if limit == 0 || board.conflict_count() > 0 {
+        return solutions;
+    }
+
+    Search::new(board).run::<C>(&mut |values| {
+        solutions.push(board.with_values(values));
+        solutions.len() < limit
+    });
+    solutions
+}
+
+// Stops counting once `limit` solutions are found
+pub fn count_solutions_with<C: CandidateSet>(board: &Sudoku, limit: usize) -> usize {
+    let mut count = 0;
+    if limit == 0 || board.conflict_count() > 0 {
+        return 0;
+    }
+
+    Search::new(board).run::<C>(&mut |_| {
+        count += 1;
+        count < limit
+    });
+    count
+}
+
+struct Search {
+    dim: usize,
+    // Numbers of the board in reading order, 0 for empty cells
+    values: Vec<u32>,
+    // Indices of the peers of each cell
+    peers: Vec<Vec<usize>>,
+    // Candidates the player or the input narrowed a cell down to, which the search sticks to
+    locked: Vec<Option<Vec<u32>>>,
+}
+
+impl Search {
+    fn new(board: &Sudoku) -> Self {
+        let dim = board.dim();
+        let mut values = vec![0; dim * dim];
+        let mut peers = vec![vec![]; dim * dim];
+        let mut locked = vec![None; dim * dim];
+
+        for y in 0..dim {
+            for x in 0..dim {
+                let cell = board.get_cell(x, y);
+                match cell.value() {
+                    CellValue::Certain(num) => values[y * dim + x] = *num,
+                    CellValue::Uncertain(nums) if cell.candidates_locked() => locked[y * dim + x] = Some(nums.clone()),
+                    CellValue::Uncertain(_) => {}
+                }
+                peers[y * dim + x] = board.peer_coords(x, y).into_iter().map(|(px, py)| py * dim + px).collect();
+            }
+        }
+
+        Self { dim, values, peers, locked }
+    }
+
+    // Calls `found` with every solution until it returns false, returns whether it did
+    fn run<C: CandidateSet>(&mut self, found: &mut dyn FnMut(&[u32]) -> bool) -> bool {
+        let mut best: Option<(usize, C)> = None;
+
+        for index in 0..self.values.len() {
+            if self.values[index] != 0 {
+                continue;
+            }
+
+            let mut candidates = C::full(self.dim as u32);
+            for &peer in &self.peers[index] {
+                candidates.remove(self.values[peer]);
+            }
+            if let Some(locked) = &self.locked[index] {
+                for num in (1..=self.dim as u32).filter(|num| !locked.contains(num)) {
+                    candidates.remove(num);
+                }
+            }
+
+            if best.as_ref().is_none_or(|(_, best)| candidates.len() < best.len()) {
+                best = Some((index, candidates));
+            }
+        }
+
+        let (index, candidates) = match best {
+            Some(v) => v,
+            None => return !found(&self.values),
+        };
+
+        for num in candidates.nums() {
+            self.values[index] = num;
+            if self.run::<C>(found) {
+                self.values[index] = 0;
+                return true;
+            }
+        }
+
+        self.values[index] = 0;
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Every cell filled from a pattern that is valid for any box shape, but the ones on every fifth diagonal
+    fn patterned_board(dim: usize) -> Sudoku {
+        let mut board = Sudoku::empty_with_dim(dim);
+        let (rows, cols) = (board.box_rows(), board.box_cols());
+        for y in 0..dim {
+            for x in (0..dim).filter(|x| (x + y) % 5 != 0) {
+                board.set_value(x, y, Some(((cols * (y % rows) + y / rows + x) % dim + 1) as u32));
+            }
+        }
+        board
+    }
+
+    #[test]
+    fn bitmask_handles_boards_32_wide() {
+        let mut candidates = Bitmask::full(32);
+        assert_eq!(candidates.len(), 32);
+        candidates.remove(32);
+        assert_eq!(candidates.nums(), (1..=31).collect::<Vec<u32>>());
+
+        let board = patterned_board(32);
+        assert_eq!(count_solutions_with::<Bitmask>(&board, 2), 1);
+        assert!(solve_with::<Bitmask>(&board).is_some_and(|solution| solution.complete()));
+    }
+
+    #[test]
+    fn representations_agree() {
+        let board = patterned_board(9);
+        let vec = solve_with::<Vec<u32>>(&board).map(|solution| solution.to_string());
+        let bitmask = solve_with::<Bitmask>(&board).map(|solution| solution.to_string());
+        assert!(vec.is_some());
+        assert_eq!(vec, bitmask);
+    }
+
+    #[test]
+    fn conflicting_boards_have_no_solution() {
+        let mut board = patterned_board(9);
+        let repeated = board.certain_value(1, 0);
+        board.set_value(0, 0, repeated);
+        assert!(board.conflict_count() > 0);
+
+        assert!(solve_with::<Vec<u32>>(&board).is_none());
+        assert!(solve_with::<Bitmask>(&board).is_none());
+        assert!(solutions_with::<Bitmask>(&board, 1).is_empty());
+    }
+}
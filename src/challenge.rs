@@ -0,0 +1,90 @@
+use crate::sudoku::{CellValue, Sudoku};
+
+pub const DEFAULT_LIVES: u32 = 3;
+
+// A game where every number entered that isn't in the solution costs a life
+#[derive(Debug, Clone)]
+pub struct Challenge {
+    solution: Sudoku,
+    lives: u32,
+    starting_lives: u32,
+}
+
+impl Challenge {
+    // Only boards with a single solution can tell a number is wrong as soon as it's entered
+    pub fn new(board: &Sudoku, lives: u32) -> Option<Self> {
+        if board.count_solutions(2) != 1 {
+            return None;
+        }
+
+        Some(Self { solution: board.solution()?, lives, starting_lives: lives })
+    }
+
+    pub fn lives(&self) -> u32 {
+        self.lives
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.lives == 0
+    }
+
+    pub fn restart(&mut self) {
+        self.lives = self.starting_lives;
+    }
+
+    pub fn is_correct(&self, x: usize, y: usize, num: u32) -> bool {
+        matches!(self.solution.get_cell(x, y).value(), CellValue::Certain(n) if *n == num)
+    }
+
+    // Numbers on the board that aren't in the solution, without telling what they should be
+    pub fn mistakes(&self, board: &Sudoku) -> Vec<(usize, usize)> {
+        board.mistakes(&self.solution)
+    }
+
+    // Puts the number on the board even when it's wrong, returns whether it was right or `None`
+    // if nothing was entered, because the game is over, the cell is a given or it already holds the number
+    pub fn enter(&mut self, board: &mut Sudoku, x: usize, y: usize, num: u32) -> Option<bool> {
+        if self.is_over() || matches!(board.get_cell(x, y).value(), CellValue::Certain(n) if *n == num) {
+            return None;
+        }
+        if !board.set_value(x, y, Some(num)) {
+            return None;
+        }
+
+        let correct = self.is_correct(x, y, num);
+        if !correct {
+            self.lives -= 1;
+        }
+        Some(correct)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wrong_entries_cost_lives_until_the_game_is_over() {
+        let mut board = Sudoku::sample("sudoku1").unwrap();
+        let mut challenge = Challenge::new(&board, 2).unwrap();
+        let answer = board.solution().unwrap().certain_value(2, 0).unwrap();
+        let wrong = answer % 9 + 1;
+
+        assert_eq!(challenge.enter(&mut board, 2, 0, answer), Some(true));
+        assert_eq!(challenge.lives(), 2);
+        assert_eq!(challenge.enter(&mut board, 2, 0, wrong), Some(false));
+        assert_eq!(challenge.lives(), 1);
+        assert!(!challenge.is_over());
+        assert_eq!(challenge.enter(&mut board, 2, 0, answer), Some(true));
+        assert_eq!(challenge.enter(&mut board, 2, 0, wrong), Some(false));
+        assert_eq!(challenge.lives(), 0);
+        assert!(challenge.is_over());
+
+        // Nothing goes in once the game is over, until it's restarted
+        assert_eq!(challenge.enter(&mut board, 2, 0, answer), None);
+        assert_eq!(board.certain_value(2, 0), Some(wrong));
+        challenge.restart();
+        assert_eq!(challenge.lives(), 2);
+        assert_eq!(challenge.enter(&mut board, 2, 0, answer), Some(true));
+    }
+}
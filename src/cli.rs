@@ -0,0 +1,832 @@
+use std::fmt;
+use std::fs;
+use std::io::{self, Write};
+use std::process;
+use std::time::{Duration, Instant};
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use sudoku_solver::candidates::CandidateRepr;
+use sudoku_solver::error::{FileError, SudokuError};
+use sudoku_solver::formats;
+use sudoku_solver::glob;
+use sudoku_solver::script::Script;
+use sudoku_solver::solver::{Solver, Step};
+use sudoku_solver::sudoku::{Deduction, Deductions, Sudoku};
+use sudoku_solver::trace::Trace;
+
+// What made a command fail, each kind exits with its own code so scripts can tell them apart
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    // Anything else, like a solve that doesn't check out
+    Failed,
+    // Flags, puzzles and other files that don't make sense
+    BadInput,
+    Unsolvable,
+    // Files that couldn't be read or written
+    Io,
+}
+
+impl ErrorKind {
+    pub fn exit_code(self) -> i32 {
+        match self {
+            ErrorKind::Failed => 1,
+            ErrorKind::BadInput => 2,
+            ErrorKind::Unsolvable => 3,
+            ErrorKind::Io => 4,
+        }
+    }
+}
+
+// An error the way the user gets to see it, as `Error: <context>: <detail>`
+#[derive(Debug, Clone)]
+pub struct CliError {
+    kind: ErrorKind,
+    context: String,
+    detail: String,
+}
+
+impl CliError {
+    pub fn new(kind: ErrorKind, context: impl Into<String>, detail: impl fmt::Display) -> Self {
+        Self { kind, context: context.into(), detail: detail.to_string() }
+    }
+
+    pub fn failed(context: impl Into<String>, detail: impl fmt::Display) -> Self {
+        Self::new(ErrorKind::Failed, context, detail)
+    }
+
+    pub fn bad_input(context: impl Into<String>, detail: impl fmt::Display) -> Self {
+        Self::new(ErrorKind::BadInput, context, detail)
+    }
+
+    pub fn unsolvable(context: impl Into<String>) -> Self {
+        Self::new(ErrorKind::Unsolvable, context, SudokuError::NoSolution)
+    }
+
+    pub fn io(context: impl Into<String>, detail: impl fmt::Display) -> Self {
+        Self::new(ErrorKind::Io, context, detail)
+    }
+
+    // Puzzle files that couldn't be read or written are IO errors and the ones that don't make sense bad input
+    pub fn file(context: impl Into<String>, err: FileError) -> Self {
+        match err {
+            FileError::Io(message) => Self::io(context, message),
+            FileError::Invalid(message) => Self::bad_input(context, message),
+        }
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    pub fn report(&self) {
+        eprintln!("Error: {}", self);
+    }
+
+    pub fn exit(&self) -> ! {
+        self.report();
+        process::exit(self.kind.exit_code());
+    }
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.context, self.detail)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Verbosity {
+    Quiet,
+    Normal,
+    Verbose,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Interactive,
+    Solve,
+    BenchCandidates,
+    Replay,
+    Convert,
+    Script,
+    Generate,
+    Snapshot,
+    Gif,
+    ReadSnapshot,
+    DumpCandidates,
+    Stress,
+}
+
+impl Mode {
+    fn needs_board(self) -> bool {
+        !matches!(self, Mode::BenchCandidates | Mode::Replay | Mode::Script | Mode::Generate | Mode::ReadSnapshot | Mode::Stress)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Options {
+    sudoku_file: Option<String>,
+    // Name of a built-in puzzle loaded instead of a file
+    sample: Option<String>,
+    // Puzzle written on a single line right on the command line
+    input_line: Option<String>,
+    // Where `--solve` writes the trace of the solve
+    trace_file: Option<String>,
+    // Where `--solve` writes how the entropy of the board went down with every step
+    csv_file: Option<String>,
+    replay_file: Option<String>,
+    // Where `--convert` writes the board, its extension picks the format
+    convert_file: Option<String>,
+    script_file: Option<String>,
+    // How many puzzles `--stress` generates and solves
+    stress_count: usize,
+    // How many cells `--solve` collapses before stopping and printing the board as it is
+    max_steps: Option<usize>,
+    // Seed of the first puzzle of `--stress`, the ones after it count up from there
+    seed: Option<u64>,
+    // Whether `--generate` makes the givens rotationally symmetric
+    symmetric: bool,
+    // Whether the solver deduces what it can before every collapse
+    propagate: bool,
+    // Deductions used when propagating, `--techniques` picks them and `--no-<name>` leaves one out
+    deductions: Deductions,
+    // Whether the solver collapses cells at random, `--no-random` always picks the smallest candidate
+    random: bool,
+    // Whether the board has the four extra regions of a Windoku
+    windoku: bool,
+    // Whether wrong numbers entered by hand cost lives
+    challenge: bool,
+    // Whether the window shows the candidates being worked out a cell at a time before each step
+    animate_sweep: bool,
+    // Whether `--solve` boxes in the quadrants of the board it prints
+    pretty: bool,
+    // Image `--snapshot` saves the solved board to, or `--read-snapshot` loads the puzzle from
+    snapshot_file: Option<String>,
+    // Animated GIF `--gif` saves the solve to
+    gif_file: Option<String>,
+    pub mode: Mode,
+    pub verbosity: Verbosity,
+}
+
+impl Options {
+    pub fn from_args<I: Iterator<Item = String>>(mut args: I) -> Result<Self, String> {
+        let mut sudoku_file = None;
+        let mut sample = None;
+        let mut input_line = None;
+        let mut trace_file = None;
+        let mut csv_file = None;
+        let mut replay_file = None;
+        let mut convert_file = None;
+        let mut script_file = None;
+        let mut stress_count = 0;
+        let mut seed = None;
+        let mut max_steps = None;
+        let mut symmetric = false;
+        let mut propagate = false;
+        let mut deductions = Deductions::all();
+        let mut random = true;
+        let mut windoku = false;
+        let mut challenge = false;
+        let mut pretty = false;
+        let mut animate_sweep = false;
+        let mut snapshot_file = None;
+        let mut gif_file = None;
+        let mut mode = Mode::Interactive;
+        let mut verbosity = Verbosity::Normal;
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--solve" => mode = Mode::Solve,
+                "--sample" => sample = Some(args.next().ok_or("Expected sample name after `--sample`")?),
+                "--input-line" => input_line = Some(args.next().ok_or("Expected a puzzle after `--input-line`")?),
+                "--bench-candidates" => mode = Mode::BenchCandidates,
+                "--trace" => trace_file = Some(args.next().ok_or("Expected trace file after `--trace`")?),
+                "--log-csv" => csv_file = Some(args.next().ok_or("Expected CSV file after `--log-csv`")?),
+                "--replay" => {
+                    mode = Mode::Replay;
+                    replay_file = Some(args.next().ok_or("Expected trace file after `--replay`")?);
+                }
+                "--convert" => {
+                    if sudoku_file.is_some() {
+                        return Err("Expected either a sudoku file or `--convert`, not both".to_string());
+                    }
+                    mode = Mode::Convert;
+                    sudoku_file = Some(args.next().ok_or("Expected input file after `--convert`")?);
+                    convert_file = Some(args.next().ok_or("Expected output file after `--convert <in>`")?);
+                }
+                "--script" => {
+                    mode = Mode::Script;
+                    script_file = Some(args.next().ok_or("Expected commands file after `--script`")?);
+                }
+                "--generate" => mode = Mode::Generate,
+                "--stress" => {
+                    mode = Mode::Stress;
+                    let count = args.next().ok_or("Expected number of puzzles after `--stress`")?;
+                    stress_count = count.parse().map_err(|_| format!("Invalid number of puzzles `{}`", count))?;
+                }
+                "--seed" => {
+                    let value = args.next().ok_or("Expected seed after `--seed`")?;
+                    seed = Some(value.parse().map_err(|_| format!("Invalid seed `{}`", value))?);
+                }
+                "--steps" => {
+                    let value = args.next().ok_or("Expected number of steps after `--steps`")?;
+                    max_steps = Some(value.parse().map_err(|_| format!("Invalid number of steps `{}`", value))?);
+                }
+                "--symmetric" => symmetric = true,
+                "--propagate" => propagate = true,
+                "--techniques" => {
+                    let list = args.next().ok_or("Expected a list of deductions after `--techniques`")?;
+                    deductions = Deductions::parse(&list)?;
+                }
+                flag if flag.strip_prefix("--no-").and_then(Deduction::from_name).is_some() => {
+                    let deduction = flag.strip_prefix("--no-").and_then(Deduction::from_name).expect("checked by the guard");
+                    deductions = deductions.without(deduction);
+                }
+                "--no-random" => random = false,
+                "--windoku" => windoku = true,
+                "--challenge" => challenge = true,
+                "--pretty" => pretty = true,
+                "--animate-sweep" => animate_sweep = true,
+                "--dump-candidates" => mode = Mode::DumpCandidates,
+                "--snapshot" => {
+                    mode = Mode::Snapshot;
+                    snapshot_file = Some(args.next().ok_or("Expected image file after `--snapshot`")?);
+                }
+                "--gif" => {
+                    mode = Mode::Gif;
+                    gif_file = Some(args.next().ok_or("Expected image file after `--gif`")?);
+                }
+                "--read-snapshot" => {
+                    mode = Mode::ReadSnapshot;
+                    snapshot_file = Some(args.next().ok_or("Expected image file after `--read-snapshot`")?);
+                }
+                "--quiet" => verbosity = Verbosity::Quiet,
+                "--verbose" => verbosity = Verbosity::Verbose,
+                flag if flag.starts_with("--") => return Err(format!("Unknown flag `{}`", flag)),
+                _ if sudoku_file.is_some() => return Err(format!("Unexpected argument `{}`", arg)),
+                _ => sudoku_file = Some(arg),
+            }
+        }
+
+        let sources = [sudoku_file.is_some(), sample.is_some(), input_line.is_some()].iter().filter(|&&given| given).count();
+        if sources == 0 && mode.needs_board() {
+            return Err("Expected sudoku file".to_string());
+        }
+        if sources > 1 {
+            return Err("Expected only one of a sudoku file, a sample or `--input-line`".to_string());
+        }
+        // A trace of a partial solve would never replay to the same board
+        if max_steps.is_some() && trace_file.is_some() {
+            return Err("Expected either `--steps` or `--trace`, not both".to_string());
+        }
+        Ok(Self {
+            sudoku_file,
+            sample,
+            input_line,
+            trace_file,
+            csv_file,
+            replay_file,
+            convert_file,
+            script_file,
+            stress_count,
+            max_steps,
+            seed,
+            symmetric,
+            propagate,
+            deductions,
+            random,
+            windoku,
+            challenge,
+            pretty,
+            animate_sweep,
+            snapshot_file,
+            gif_file,
+            mode,
+            verbosity,
+        })
+    }
+
+    pub fn sudoku_file(&self) -> Option<&str> {
+        self.sudoku_file.as_deref()
+    }
+
+    // How the loaded board is referred to in messages
+    pub fn source_name(&self) -> &str {
+        let input_line = self.input_line.as_ref().map(|_| "--input-line");
+        self.sample.as_deref().or(self.sudoku_file.as_deref()).or(input_line).expect("mode requires a board")
+    }
+
+    pub fn is_challenge(&self) -> bool {
+        self.challenge
+    }
+
+    pub fn animates_sweep(&self) -> bool {
+        self.animate_sweep
+    }
+
+    pub fn snapshot_file(&self) -> Option<&str> {
+        self.snapshot_file.as_deref()
+    }
+
+    pub fn gif_file(&self) -> Option<&str> {
+        self.gif_file.as_deref()
+    }
+
+    // Applies the solving flags to a solver
+    pub fn configure(&self, solver: &mut Solver) {
+        solver.set_propagate(self.propagate);
+        solver.set_deductions(self.deductions);
+        solver.set_random(self.random);
+    }
+
+    // A solver for `board` set up by the flags, seeded with `--seed` when there is one
+    pub fn solver(&self, board: Sudoku) -> Solver {
+        let mut solver = match self.seed {
+            Some(seed) => Solver::with_seed(board, seed),
+            None => Solver::new(board),
+        };
+        self.configure(&mut solver);
+        solver
+    }
+
+    pub fn is_verbose(&self) -> bool {
+        self.verbosity >= Verbosity::Verbose
+    }
+
+    pub fn is_quiet(&self) -> bool {
+        self.verbosity <= Verbosity::Quiet
+    }
+}
+
+// Exits right away if the board can't be loaded
+pub fn load_board(options: &Options) -> Sudoku {
+    try_load_board(options).unwrap_or_else(|err| err.exit())
+}
+
+pub fn try_load_board(options: &Options) -> Result<Sudoku, CliError> {
+    let mut board = if let Some(name) = &options.sample {
+        match Sudoku::sample(name) {
+            Some(v) => v,
+            None => {
+                let names = Sudoku::sample_names().join(", ");
+                return Err(CliError::bad_input(format!("Unknown sample `{}`", name), format!("expected one of: {}", names)));
+            }
+        }
+    } else if let Some(line) = &options.input_line {
+        let invalid = |err: SudokuError| CliError::bad_input("Invalid `--input-line`", err);
+        let board = line.parse::<Sudoku>().map_err(invalid)?;
+        board.validate_givens().map_err(invalid)?;
+        board
+    } else {
+        formats::load(options.source_name()).map_err(|err| CliError::file(format!("Could not read file `{}`", options.source_name()), err))?
+    };
+
+    if options.windoku {
+        board.set_windoku(true).map_err(|err| CliError::bad_input(format!("Sudoku `{}`", options.source_name()), err))?;
+    }
+
+    // There is nothing left to collapse on a filled board, so it can only be checked
+    if board.is_filled() {
+        if let Err(err) = board.check_complete() {
+            return Err(CliError::bad_input(format!("Sudoku `{}` is filled but not valid", options.source_name()), err));
+        }
+    }
+
+    Ok(board)
+}
+
+pub fn run_solve(options: &Options) -> Result<(), CliError> {
+    solve_to(options, &mut io::stdout().lock())
+}
+
+// Solves every file matching the pattern given instead of a file, one after the other, writing
+// everything but the errors to `out`
+fn solve_to<W: Write>(options: &Options, out: &mut W) -> Result<(), CliError> {
+    let pattern = match options.sudoku_file() {
+        Some(pattern) if glob::is_pattern(pattern) => pattern,
+        _ => return solve_board(options, out),
+    };
+
+    let files = glob::expand(pattern).map_err(|message| CliError::io("Could not read files", message))?;
+    let single_file_flag = match (&options.trace_file, &options.csv_file) {
+        (Some(_), _) => Some("--trace"),
+        (_, Some(_)) => Some("--log-csv"),
+        _ => None,
+    };
+    if let Some(flag) = single_file_flag.filter(|_| files.len() > 1) {
+        let context = format!("Expected a single sudoku file with `{}`", flag);
+        return Err(CliError::bad_input(context, format!("`{}` matches {}", pattern, files.len())));
+    }
+
+    let mut solved = 0;
+    // The whole run exits with the code of the first puzzle that failed
+    let mut first_failure = None;
+    for file in &files {
+        if !options.is_quiet() {
+            writeln!(out, "{}:", file).map_err(output_error)?;
+        }
+
+        let file_options = Options { sudoku_file: Some(file.clone()), ..options.clone() };
+        match solve_board(&file_options, out) {
+            Ok(()) => solved += 1,
+            Err(err) => {
+                err.report();
+                first_failure = first_failure.or(Some(err.kind()));
+            }
+        }
+    }
+
+    if !options.is_quiet() {
+        writeln!(out, "Solved {} of {} puzzles", solved, files.len()).map_err(output_error)?;
+    }
+    match first_failure {
+        Some(kind) => {
+            let failed = files.len() - solved;
+            Err(CliError::new(kind, format!("Pattern `{}`", pattern), format!("{} of {} puzzles failed", failed, files.len())))
+        }
+        None => Ok(()),
+    }
+}
+
+fn output_error(err: io::Error) -> CliError {
+    CliError::io("Could not write the output", err)
+}
+
+fn solve_board<W: Write>(options: &Options, out: &mut W) -> Result<(), CliError> {
+    let board = try_load_board(options)?;
+
+    if board.complete() {
+        if !options.is_quiet() {
+            writeln!(out, "Sudoku `{}` is already solved", options.source_name()).map_err(output_error)?;
+            print_board(options, &board, out)?;
+        }
+        return Ok(());
+    }
+
+    // Collapsing an unsolvable board would go through every possibility before giving up
+    if board.count_solutions(1) == 0 {
+        if let Some(givens) = board.explain_unsolvable() {
+            let coords: Vec<String> = givens.iter().map(|(x, y)| format!("({}, {})", x, y)).collect();
+            eprintln!("Note: Removing the givens at {} would make it solvable", coords.join(", "));
+        }
+        return Err(CliError::unsolvable(format!("Sudoku `{}`", options.source_name())));
+    }
+
+    let mut solver = options.solver(board.clone());
+    let mut step_count = 0;
+    let mut csv = String::from("step,entropy,filled_fraction,depth\n");
+    // The steps are written from inside the solve, the first error is only reported once it's over
+    let mut write_result = Ok(());
+
+    let solved = solver.solve_observing_up_to(options.max_steps, |step, solver| {
+        step_count += 1;
+        if options.csv_file.is_some() {
+            let board = solver.board();
+            csv += &format!("{},{},{:.4},{}\n", step_count, board.total_entropy(), board.filled_fraction(), solver.depth());
+        }
+        if !options.is_verbose() {
+            return;
+        }
+
+        let line = match step {
+            Step::Collapsed { x, y, digit } => format!("step {}: collapsed ({}, {}) to {}", step_count, x, y, digit),
+            Step::Backtracked => format!("step {}: backtracked", step_count),
+            Step::Complete | Step::Unsolvable => return,
+        };
+        if write_result.is_ok() {
+            write_result = writeln!(out, "{}", line);
+        }
+    });
+    write_result.map_err(output_error)?;
+
+    if let Some(csv_file) = &options.csv_file {
+        fs::write(csv_file, csv).map_err(|err| CliError::io(format!("Could not write `{}`", csv_file), err))?;
+    }
+
+    let Some(solved) = solved else {
+        if !options.is_quiet() {
+            writeln!(out, "Stopped early, {}", solver.status()).map_err(output_error)?;
+            print_board(options, solver.board(), out)?;
+        }
+        return Ok(());
+    };
+    if !solved {
+        return Err(CliError::unsolvable(format!("Sudoku `{}`", options.source_name())));
+    }
+
+    if let Some(trace_file) = &options.trace_file {
+        let json = Trace::record(&board, &solver).to_json();
+        fs::write(trace_file, json).map_err(|err| CliError::io(format!("Could not write trace `{}`", trace_file), err))?;
+    }
+
+    if options.is_verbose() {
+        writeln!(out, "{}", solver.status()).map_err(output_error)?;
+    }
+    if !options.is_quiet() {
+        print_board(options, solver.board(), out)?;
+    }
+    Ok(())
+}
+
+fn print_board<W: Write>(options: &Options, board: &Sudoku, out: &mut W) -> Result<(), CliError> {
+    let result = if options.pretty { write!(out, "{:#}", board) } else { write!(out, "{}", board) };
+    result.map_err(output_error)
+}
+
+pub fn run_replay(options: &Options) -> Result<(), CliError> {
+    let replay_file = options.replay_file.as_deref().expect("replay mode requires a trace file");
+    let context = || format!("Could not read trace `{}`", replay_file);
+
+    let text = fs::read_to_string(replay_file).map_err(|err| CliError::io(context(), err))?;
+    let trace = Trace::from_json(&text).map_err(|message| CliError::bad_input(context(), message))?;
+
+    let board = trace.replay().map_err(|message| CliError::failed(format!("Trace `{}` does not replay", replay_file), message))?;
+    if options.is_verbose() {
+        println!("Replayed {} steps with seed {}", trace.steps.len(), trace.seed);
+    }
+    if !options.is_quiet() {
+        print!("{}", board);
+    }
+    Ok(())
+}
+
+pub fn run_convert(options: &Options) -> Result<(), CliError> {
+    let convert_file = options.convert_file.as_deref().expect("convert mode requires an output file");
+    let board = try_load_board(options)?;
+
+    formats::save(&board, convert_file).map_err(|err| CliError::file(format!("Could not write `{}`", convert_file), err))?;
+
+    if options.is_verbose() {
+        println!("Converted `{}` to `{}`", options.source_name(), convert_file);
+    }
+    Ok(())
+}
+
+pub fn run_script(options: &Options) -> Result<(), CliError> {
+    let script_file = options.script_file.as_deref().expect("script mode requires a commands file");
+    let context = || format!("Could not read script `{}`", script_file);
+
+    let text = fs::read_to_string(script_file).map_err(|err| CliError::io(context(), err))?;
+    let script = Script::parse(&text).map_err(|message| CliError::bad_input(context(), message))?;
+
+    // A board given on the command line is loaded before the first command
+    let has_board = options.sudoku_file.is_some() || options.sample.is_some() || options.input_line.is_some();
+    let mut solver = match has_board {
+        true => Some(options.solver(try_load_board(options)?)),
+        false => None,
+    };
+
+    let new_solver = |board| options.solver(board);
+    let result = if options.is_quiet() {
+        script.run(&mut solver, new_solver, &mut std::io::sink())
+    } else {
+        script.run(&mut solver, new_solver, &mut std::io::stdout())
+    };
+
+    result.map_err(|message| CliError::failed(format!("Script `{}` failed", script_file), message))
+}
+
+pub fn run_generate(options: &Options) {
+    let seed = options.seed.unwrap_or_else(|| rand::thread_rng().gen());
+    let puzzle = Sudoku::generate(Sudoku::BOARD_DIM, options.symmetric, &mut StdRng::seed_from_u64(seed));
+
+    if options.is_verbose() {
+        let givens = puzzle.static_cells().count();
+        let symmetry = if puzzle.has_rotational_symmetry() { "symmetric" } else { "asymmetric" };
+        let minimal = if puzzle.is_minimal() { "minimal" } else { "not minimal" };
+        println!("Generated a {} puzzle with {} givens, {}", symmetry, givens, minimal);
+    }
+    print!("{}", puzzle);
+}
+
+// Generates puzzles and solves them, checking every solution, each puzzle is generated and solved
+// with its own seed so a failing one can be tried again alone with `--stress 1 --seed <seed>`
+pub fn run_stress(options: &Options) -> Result<(), CliError> {
+    let first_seed = options.seed.unwrap_or_else(|| rand::thread_rng().gen());
+    let mut failures = 0;
+
+    for i in 0..options.stress_count {
+        let seed = first_seed.wrapping_add(i as u64);
+        let result = stress_one(options, seed);
+
+        if let Err(message) = &result {
+            failures += 1;
+            CliError::failed(format!("Puzzle {} with seed {}", i + 1, seed), message).report();
+        } else if options.is_verbose() {
+            println!("puzzle {}: seed {} solved", i + 1, seed);
+        }
+    }
+
+    if !options.is_quiet() {
+        println!("{} of {} puzzles failed, starting from seed {}", failures, options.stress_count, first_seed);
+    }
+    if failures > 0 {
+        return Err(CliError::failed("Stress test", format!("{} of {} puzzles failed", failures, options.stress_count)));
+    }
+    Ok(())
+}
+
+fn stress_one(options: &Options, seed: u64) -> Result<(), String> {
+    let puzzle = Sudoku::generate(Sudoku::BOARD_DIM, options.symmetric, &mut StdRng::seed_from_u64(seed));
+
+    let mut solver = Solver::with_seed(puzzle.clone(), seed);
+    options.configure(&mut solver);
+    if !solver.solve_observing(|_, _| {}) {
+        return Err("the solver found no solution".to_string());
+    }
+
+    let solution = solver.board();
+    solution.check_complete().map_err(|err| format!("the solution is not valid: {}", err))?;
+    if let Some((x, y)) = puzzle.static_cells().find(|&(x, y)| solution.certain_value(x, y) != puzzle.certain_value(x, y)) {
+        return Err(format!("the solution changed the given at ({}, {})", x, y));
+    }
+
+    Ok(())
+}
+
+pub fn run_dump_candidates(options: &Options) -> Result<(), CliError> {
+    let mut board = try_load_board(options)?;
+    board.update_possible_values();
+    if options.propagate {
+        board.solve_logically_with(options.deductions);
+    }
+    println!("{}", board.candidates_to_json());
+    Ok(())
+}
+
+// Puzzles known for requiring a lot of backtracking
+const BENCH_PUZZLES: &[&str] = &[
+    "8........\n..36.....\n.7..9.2..\n.5...7...\n....457..\n...1...3.\n..1....68\n..85...1.\n.9....4..\n",
+    "1....7.9.\n.3..2...8\n..96..5..\n..53..9..\n.1..8...2\n6....4...\n3......1.\n.4......7\n..7...3..\n",
+];
+
+fn time_solve(repr: CandidateRepr, board: &Sudoku) -> (Option<Sudoku>, Duration) {
+    let start = Instant::now();
+    let solution = repr.solve(board);
+    (solution, start.elapsed())
+}
+
+pub fn run_bench_candidates(options: &Options) -> Result<(), CliError> {
+    let mut vec_total = Duration::ZERO;
+    let mut bitmask_total = Duration::ZERO;
+
+    for (i, puzzle) in BENCH_PUZZLES.iter().enumerate() {
+        let board = Sudoku::from_text(puzzle);
+        let (vec_solution, vec_time) = time_solve(CandidateRepr::Vec, &board);
+        let (bitmask_solution, bitmask_time) = time_solve(CandidateRepr::Bitmask, &board);
+
+        let (vec_solution, bitmask_solution) = match (vec_solution, bitmask_solution) {
+            (Some(a), Some(b)) => (a.to_string(), b.to_string()),
+            _ => return Err(CliError::failed(format!("Benchmark puzzle {}", i + 1), "no solution was found")),
+        };
+
+        if vec_solution != bitmask_solution {
+            return Err(CliError::failed(format!("Benchmark puzzle {}", i + 1), "the candidate representations disagree"));
+        }
+
+        vec_total += vec_time;
+        bitmask_total += bitmask_time;
+
+        if options.is_verbose() {
+            print!("{}", bitmask_solution);
+        }
+        if !options.is_quiet() {
+            println!(
+                "puzzle {}: vec {:.2?}, bitmask {:.2?}, speedup {:.2}x",
+                i + 1,
+                vec_time,
+                bitmask_time,
+                vec_time.as_secs_f64() / bitmask_time.as_secs_f64()
+            );
+        }
+    }
+
+    if !options.is_quiet() {
+        println!(
+            "total: vec {:.2?}, bitmask {:.2?}, speedup {:.2}x",
+            vec_total,
+            bitmask_total,
+            vec_total.as_secs_f64() / bitmask_total.as_secs_f64()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(args: &[&str]) -> Options {
+        Options::from_args(args.iter().map(|arg| arg.to_string())).unwrap()
+    }
+
+    #[test]
+    fn stress_test_finds_no_failures() {
+        for args in [&["--stress", "5", "--seed", "7", "--quiet"][..], &["--stress", "5", "--seed", "7", "--quiet", "--symmetric"]] {
+            let options = options(args);
+            for seed in 7..12 {
+                assert_eq!(stress_one(&options, seed), Ok(()), "seed {}", seed);
+            }
+            assert!(run_stress(&options).is_ok());
+        }
+    }
+
+    #[test]
+    fn quiet_solves_write_nothing() {
+        let dir = std::env::temp_dir().join(format!("sudoku-quiet-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("puzzle.txt");
+        fs::write(&path, Sudoku::sample("sudoku1").unwrap().to_string()).unwrap();
+        let path = path.to_str().unwrap();
+
+        let mut out = vec![];
+        solve_to(&options(&["--solve", "--quiet", path]), &mut out).unwrap();
+        assert!(out.is_empty(), "wrote {:?}", String::from_utf8_lossy(&out));
+
+        // Without it the solution is written, and with `--verbose` every step before it
+        solve_to(&options(&["--solve", path]), &mut out).unwrap();
+        let solved = String::from_utf8(std::mem::take(&mut out)).unwrap();
+        assert_eq!(formats::Format::Text.parse(&solved).map(|board| board.complete()), Ok(true));
+        solve_to(&options(&["--solve", "--verbose", path]), &mut out).unwrap();
+        assert!(String::from_utf8(out).unwrap().starts_with("step 1: collapsed"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn every_kind_has_its_own_exit_code() {
+        let kinds = [ErrorKind::Failed, ErrorKind::BadInput, ErrorKind::Unsolvable, ErrorKind::Io];
+        let codes: Vec<i32> = kinds.iter().map(|kind| kind.exit_code()).collect();
+        assert_eq!(codes, [1, 2, 3, 4]);
+
+        assert_eq!(CliError::failed("", "").kind(), ErrorKind::Failed);
+        assert_eq!(CliError::bad_input("", "").kind(), ErrorKind::BadInput);
+        assert_eq!(CliError::unsolvable("").kind(), ErrorKind::Unsolvable);
+        assert_eq!(CliError::io("", "").kind(), ErrorKind::Io);
+        assert_eq!(CliError::file("", FileError::Io(String::new())).kind(), ErrorKind::Io);
+        assert_eq!(CliError::file("", FileError::Invalid(String::new())).kind(), ErrorKind::BadInput);
+    }
+
+    #[test]
+    fn solving_files_fails_with_the_right_kind() {
+        let dir = std::env::temp_dir().join(format!("sudoku-cli-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let solve = |name: &str, text: Option<&str>| {
+            let path = dir.join(name);
+            if let Some(text) = text {
+                fs::write(&path, text).unwrap();
+            }
+            run_solve(&options(&["--solve", "--quiet", path.to_str().unwrap()]))
+        };
+
+        let solvable = Sudoku::sample("sudoku1").unwrap().to_string();
+        assert!(solve("solvable.txt", Some(&solvable)).is_ok());
+        assert_eq!(solve("missing.txt", None).unwrap_err().kind(), ErrorKind::Io);
+        assert_eq!(solve("garbage.txt", Some("not a sudoku\n")).unwrap_err().kind(), ErrorKind::BadInput);
+        // Nothing repeats, but the last cell of the first row has no number left
+        let unsolvable = format!("12345678.\n........9\n{}", ".........\n".repeat(7));
+        assert_eq!(solve("unsolvable.txt", Some(&unsolvable)).unwrap_err().kind(), ErrorKind::Unsolvable);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn solves_without_randomness_the_same_way() {
+        let solve = || {
+            let mut out = vec![];
+            solve_to(&options(&["--solve", "--no-random", "--verbose", "--sample", "sudoku2"]), &mut out).unwrap();
+            out
+        };
+        let first = solve();
+        assert!(!first.is_empty());
+        assert_eq!(first, solve());
+    }
+
+    #[test]
+    fn logs_a_csv_row_per_step() {
+        let dir = std::env::temp_dir().join(format!("sudoku-csv-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let csv_file = dir.join("steps.csv");
+
+        let mut out = vec![];
+        let args = ["--solve", "--verbose", "--seed", "3", "--log-csv", csv_file.to_str().unwrap(), "--sample", "sudoku2"];
+        solve_to(&options(&args), &mut out).unwrap();
+        let steps = String::from_utf8(out).unwrap().lines().filter(|line| line.starts_with("step ")).count();
+
+        let csv = fs::read_to_string(&csv_file).unwrap();
+        let mut lines = csv.lines();
+        assert_eq!(lines.next(), Some("step,entropy,filled_fraction,depth"));
+        let rows: Vec<Vec<&str>> = lines.map(|line| line.split(',').collect()).collect();
+        // The step that finds the board complete gets a row too
+        assert_eq!(rows.len(), steps + 1);
+        for (i, row) in rows.iter().enumerate() {
+            assert_eq!(row.len(), 4);
+            assert_eq!(row[0], (i + 1).to_string());
+        }
+        assert_eq!(rows.last().map(|row| (row[1], row[2])), Some(("0", "1.0000")));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,92 @@
+use std::error::Error;
+use std::fmt;
+
+use crate::sudoku::Unit;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SudokuError {
+    InvalidDigit { x: usize, y: usize, digit: u32 },
+    DuplicateGiven { x: usize, y: usize, digit: u32 },
+    InvalidFormat(String),
+    DimensionMismatch { expected: usize, found: usize },
+    ConflictingCell { x: usize, y: usize, ours: u32, theirs: u32 },
+    InconsistentBoard,
+    NoSolution,
+}
+
+impl fmt::Display for SudokuError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SudokuError::InvalidDigit { x, y, digit } => {
+                write!(f, "invalid digit {} at ({}, {})", digit, x, y)
+            }
+            SudokuError::DuplicateGiven { x, y, digit } => {
+                write!(f, "given {} at ({}, {}) is repeated in its row, column or quadrant", digit, x, y)
+            }
+            SudokuError::InvalidFormat(message) => write!(f, "{}", message),
+            SudokuError::DimensionMismatch { expected, found } => {
+                write!(f, "expected a {0}x{0} board but found a {1}x{1} one", expected, found)
+            }
+            SudokuError::ConflictingCell { x, y, ours, theirs } => {
+                write!(f, "cell ({}, {}) is {} on one board and {} on the other", x, y, ours, theirs)
+            }
+            SudokuError::InconsistentBoard => {
+                write!(f, "a number is repeated in a row, column or quadrant")
+            }
+            SudokuError::NoSolution => write!(f, "the board has no solution"),
+        }
+    }
+}
+
+impl Error for SudokuError {}
+
+// Why a puzzle couldn't be loaded or saved, telling files that couldn't be read or written
+// apart from the ones that don't make sense
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FileError {
+    Io(String),
+    Invalid(String),
+}
+
+impl fmt::Display for FileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FileError::Io(message) | FileError::Invalid(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl Error for FileError {}
+
+impl From<FileError> for String {
+    fn from(err: FileError) -> Self {
+        err.to_string()
+    }
+}
+
+// Why a board isn't a valid solution
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CompletionError {
+    Incomplete { x: usize, y: usize },
+    Duplicate { unit: Unit, digit: u32 },
+    SandwichMismatch { line: Unit, clue: u32 },
+    // The cell isn't above the one before it on the thermometer
+    ThermometerNotRising { thermometer: usize, x: usize, y: usize },
+}
+
+impl fmt::Display for CompletionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CompletionError::Incomplete { x, y } => write!(f, "cell ({}, {}) is not filled in", x, y),
+            CompletionError::Duplicate { unit, digit } => write!(f, "{} has {} more than once", unit, digit),
+            CompletionError::SandwichMismatch { line, clue } => {
+                write!(f, "the numbers between the 1 and the highest number of {} don't add up to {}", line, clue)
+            }
+            CompletionError::ThermometerNotRising { thermometer, x, y } => {
+                write!(f, "cell ({}, {}) isn't higher than the one before it on thermometer {}", x, y, thermometer)
+            }
+        }
+    }
+}
+
+impl Error for CompletionError {}
@@ -0,0 +1,245 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::FileError;
+use crate::json::Json;
+use crate::sandwich::SandwichClues;
+use crate::thermo::Thermometers;
+use crate::sudoku::{CellValue, Sudoku};
+
+// File formats puzzles can be read from and written to, told apart by their extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    // One row per line, as in `samples/`
+    Text,
+    // SadMan Sudoku, rows like plain text with `#` comment lines and `[Section]` headers
+    Sdk,
+    // The whole board in a single line
+    Line,
+    // `{"board": [[5, 3, null, ...], ...]}` with `null` for empty cells, or an array of
+    // the only candidates the cell can have
+    Json,
+    // Simple Sudoku, cells separated by spaces and the rows of quadrants by blank lines
+    Ss,
+}
+
+impl Format {
+    pub fn from_path(path: &str) -> Result<Self, String> {
+        let extension = Path::new(path)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.to_ascii_lowercase());
+
+        match extension.as_deref() {
+            Some("txt") | None => Ok(Format::Text),
+            Some("sdk") => Ok(Format::Sdk),
+            Some("line") | Some("sdm") => Ok(Format::Line),
+            Some("json") => Ok(Format::Json),
+            Some("ss") => Ok(Format::Ss),
+            Some(ext) => Err(format!("unknown puzzle format `.{}`", ext)),
+        }
+    }
+
+    pub fn parse(self, text: &str) -> Result<Sudoku, String> {
+        match self {
+            Format::Text => {
+                Sudoku::detect_dimension(text)?;
+                Ok(Sudoku::from_text(text))
+            }
+            Format::Sdk => {
+                let rows: Vec<&str> = text
+                    .lines()
+                    .filter(|line| !line.starts_with('#') && !line.starts_with('['))
+                    .collect();
+                let rows = rows.join("\n");
+                Sudoku::detect_dimension(&rows)?;
+                Ok(Sudoku::from_text(&rows))
+            }
+            Format::Line => {
+                let line = text.lines().find(|line| !line.trim().is_empty()).unwrap_or("");
+                line.parse().map_err(|e: crate::error::SudokuError| e.to_string())
+            }
+            Format::Json => parse_json(text),
+            Format::Ss => Sudoku::from_ss_text(text),
+        }
+    }
+
+    pub fn write(self, board: &Sudoku) -> String {
+        match self {
+            Format::Text | Format::Sdk => board.to_string(),
+            Format::Line => format!("{}\n", board.to_line()),
+            Format::Json => format!("{}\n", to_json(board)),
+            Format::Ss => board.to_ss(),
+        }
+    }
+}
+
+// Sandwich clues of a puzzle are kept next to it, in a file with the same name and a `.sandwich` extension
+fn sandwich_path(path: &str) -> PathBuf {
+    Path::new(path).with_extension("sandwich")
+}
+
+// And so are its thermometers, with a `.thermo` extension
+fn thermo_path(path: &str) -> PathBuf {
+    Path::new(path).with_extension("thermo")
+}
+
+pub fn load(path: &str) -> Result<Sudoku, FileError> {
+    let format = Format::from_path(path).map_err(FileError::Invalid)?;
+    let text = fs::read_to_string(path).map_err(|e| FileError::Io(e.to_string()))?;
+    let mut board = format.parse(&text).map_err(FileError::Invalid)?;
+
+    let sandwich_file = sandwich_path(path);
+    if sandwich_file.exists() {
+        let describe = |message: String| format!("{}: {}", sandwich_file.display(), message);
+        let text = fs::read_to_string(&sandwich_file).map_err(|e| FileError::Io(describe(e.to_string())))?;
+        let clues = SandwichClues::parse(&text).map_err(|e| FileError::Invalid(describe(e)))?;
+        board.set_sandwich_clues(clues).map_err(|e| FileError::Invalid(describe(e)))?;
+    }
+
+    let thermo_file = thermo_path(path);
+    if thermo_file.exists() {
+        let describe = |message: String| format!("{}: {}", thermo_file.display(), message);
+        let text = fs::read_to_string(&thermo_file).map_err(|e| FileError::Io(describe(e.to_string())))?;
+        let thermometers = Thermometers::parse(&text).map_err(|e| FileError::Invalid(describe(e)))?;
+        board.set_thermometers(thermometers).map_err(|e| FileError::Invalid(describe(e)))?;
+    }
+
+    Ok(board)
+}
+
+pub fn save(board: &Sudoku, path: &str) -> Result<(), FileError> {
+    let format = Format::from_path(path).map_err(FileError::Invalid)?;
+    let write = |path: &Path, text: String| fs::write(path, text).map_err(|e| FileError::Io(e.to_string()));
+    write(Path::new(path), format.write(board))?;
+
+    if let Some(clues) = board.sandwich_clues() {
+        write(&sandwich_path(path), clues.to_text())?;
+    }
+    if let Some(thermometers) = board.thermometers() {
+        write(&thermo_path(path), thermometers.to_text())?;
+    }
+    Ok(())
+}
+
+fn to_json(board: &Sudoku) -> Json {
+    let rows = board
+        .rows()
+        .map(|row| {
+            let cells = row
+                .iter()
+                .map(|cell| match cell.value() {
+                    CellValue::Certain(num) => Json::Number(*num as f64),
+                    CellValue::Uncertain(nums) if cell.candidates_locked() => {
+                        Json::Array(nums.iter().map(|&num| Json::Number(num as f64)).collect())
+                    }
+                    CellValue::Uncertain(_) => Json::Null,
+                })
+                .collect();
+            Json::Array(cells)
+        })
+        .collect();
+
+    Json::Object(vec![("board".to_string(), Json::Array(rows))])
+}
+
+fn parse_json(text: &str) -> Result<Sudoku, String> {
+    let json = Json::parse(text)?;
+    let rows = json
+        .get("board")
+        .and_then(Json::as_array)
+        .ok_or("expected a `board` array of rows")?;
+
+    // Goes through the plain text format so both share the same validation
+    let mut board_text = String::new();
+    let mut annotated = vec![];
+    for (y, row) in rows.iter().enumerate() {
+        for (x, cell) in row.as_array().ok_or("expected every row to be an array")?.iter().enumerate() {
+            match cell {
+                Json::Null => board_text.push('.'),
+                Json::Array(nums) => {
+                    let nums: Vec<u32> = nums
+                        .iter()
+                        .map(|num| num.as_u64().map(|num| num as u32).ok_or(format!("invalid candidate `{}`", num)))
+                        .collect::<Result<_, String>>()?;
+                    annotated.push((x, y, nums));
+                    board_text.push('.');
+                }
+                Json::Number(_) => {
+                    let digit = cell
+                        .as_u64()
+                        .and_then(|num| char::from_digit(num as u32, 36))
+                        .ok_or(format!("invalid cell `{}`", cell))?;
+                    board_text.push(digit);
+                }
+                _ => return Err(format!("invalid cell `{}`", cell)),
+            }
+        }
+        board_text.push('\n');
+    }
+
+    Sudoku::detect_dimension(&board_text)?;
+    let mut board = Sudoku::from_text(&board_text);
+    for (x, y, nums) in annotated {
+        if !board.set_candidates(x, y, &nums) {
+            return Err(format!("invalid candidates for cell ({}, {})", x, y));
+        }
+    }
+    Ok(board)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::Solver;
+
+    #[test]
+    fn round_trips_sdk_through_a_line() {
+        let sdk = format!("#A sample puzzle\n[Puzzle]\n{}", Sudoku::sample("sudoku1").unwrap());
+        let board = Format::Sdk.parse(&sdk).unwrap();
+        let line = Format::Line.write(&board);
+        assert_eq!(line.trim().chars().count(), 81);
+
+        let back = Format::Line.parse(&line).unwrap();
+        assert_eq!(Format::Sdk.write(&back), Format::Sdk.write(&board));
+        assert_eq!(back.static_cells().count(), board.static_cells().count());
+    }
+
+    #[test]
+    fn rejects_lines_of_unsupported_sizes() {
+        // 7x7 boards would have quadrants a single row high, and 26x26 is past the largest size
+        for dim in [7, 26] {
+            assert!(Format::Line.parse(&".".repeat(dim * dim)).is_err(), "{0}x{0} was accepted", dim);
+        }
+        assert!(Format::Line.parse(&".".repeat(16 * 16)).is_ok());
+    }
+
+    #[test]
+    fn loads_the_simple_sudoku_fixture() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/samples/sudoku1.ss");
+        let board = load(path).unwrap();
+        let sample = Sudoku::sample("sudoku1").unwrap();
+        assert_eq!(board.to_string(), sample.to_string());
+        assert_eq!(board.static_cells().collect::<Vec<_>>(), sample.static_cells().collect::<Vec<_>>());
+
+        assert_eq!(Format::Ss.write(&board), fs::read_to_string(path).unwrap());
+    }
+
+    #[test]
+    fn solves_within_the_candidates_of_the_input() {
+        let json = r#"{"board": [[[3], null, null, null], [null, null, [1, 2], null], [null, null, null, null], [null, null, null, 4]]}"#;
+        let board = Format::Json.parse(json).unwrap();
+
+        for seed in 0..10 {
+            let mut solver = Solver::with_seed(board.clone(), seed);
+            assert!(solver.solve());
+            assert_eq!(solver.board().certain_value(0, 0), Some(3), "seed {}", seed);
+            assert!([1, 2].map(Some).contains(&solver.board().certain_value(2, 1)), "seed {}", seed);
+        }
+        assert_eq!(board.solution().unwrap().certain_value(0, 0), Some(3));
+
+        // A 4 is the only number that fits next to the others, and it was left out
+        let json = r#"{"board": [[1, 2, 3, [1, 2]], [null, null, null, null], [null, null, null, null], [null, null, null, null]]}"#;
+        assert!(!Format::Json.parse(json).unwrap().is_solvable());
+    }
+}
@@ -0,0 +1,249 @@
+use std::collections::HashMap;
+
+// Palette indices are written in codes of at least this many bits plus one
+const MIN_CODE_SIZE: u8 = 8;
+// Codes never get longer than 12 bits, the dictionary starts over once they would
+const MAX_CODES: u16 = 4096;
+// Shades of gray after the color cube, so text and grid lines keep their antialiasing
+const GRAYS: u32 = 40;
+
+// An animated GIF that loops forever, built a frame at a time
+// Every frame uses the same palette, a cube of 6 levels per channel followed by grays,
+// which keeps colors close enough for boards without looking at the frames first
+#[derive(Debug, Clone)]
+pub struct Gif {
+    width: u16,
+    height: u16,
+    // How long each frame is shown, in hundredths of a second
+    delay: u16,
+    // Every frame already encoded, from its graphic control extension to its last data block
+    frames: Vec<Vec<u8>>,
+    // Palette index of every color seen so far, a lot of pixels share the same few colors
+    indices: HashMap<[u8; 3], u8>,
+}
+
+impl Gif {
+    pub fn new(width: u16, height: u16, delay: u16) -> Self {
+        Self { width, height, delay, frames: vec![], indices: HashMap::new() }
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.frames.len()
+    }
+
+    // `rgb` holds 3 bytes for every pixel, with rows `pitch` bytes apart
+    pub fn add_frame(&mut self, rgb: &[u8], pitch: usize) {
+        let (width, height) = (self.width as usize, self.height as usize);
+        let mut pixels = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                let i = y * pitch + x * 3;
+                let color = [rgb[i], rgb[i + 1], rgb[i + 2]];
+                pixels.push(*self.indices.entry(color).or_insert_with(|| nearest_index(color)));
+            }
+        }
+
+        let mut frame = vec![];
+        // Graphic control extension, nothing transparent and every frame replaces the last one
+        frame.extend_from_slice(&[0x21, 0xf9, 4, 0x04]);
+        frame.extend_from_slice(&self.delay.to_le_bytes());
+        frame.extend_from_slice(&[0, 0]);
+
+        // Image descriptor covering the whole image, using the global palette
+        frame.push(0x2c);
+        for value in [0, 0, self.width, self.height] {
+            frame.extend_from_slice(&value.to_le_bytes());
+        }
+        frame.push(0);
+
+        frame.push(MIN_CODE_SIZE);
+        for block in lzw_encode(&pixels).chunks(255) {
+            frame.push(block.len() as u8);
+            frame.extend_from_slice(block);
+        }
+        frame.push(0);
+
+        self.frames.push(frame);
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = b"GIF89a".to_vec();
+        bytes.extend_from_slice(&self.width.to_le_bytes());
+        bytes.extend_from_slice(&self.height.to_le_bytes());
+        // A global palette of 256 colors, background color 0 and square pixels
+        bytes.extend_from_slice(&[0xf7, 0, 0]);
+        for index in 0..=255 {
+            bytes.extend_from_slice(&palette_color(index));
+        }
+
+        // Netscape extension, repeating the animation forever
+        bytes.extend_from_slice(&[0x21, 0xff, 11]);
+        bytes.extend_from_slice(b"NETSCAPE2.0");
+        bytes.extend_from_slice(&[3, 1, 0, 0, 0]);
+
+        for frame in &self.frames {
+            bytes.extend_from_slice(frame);
+        }
+        bytes.push(0x3b);
+        bytes
+    }
+}
+
+// Channel value of each of the 6 levels of the color cube
+fn cube_level(level: u32) -> u8 {
+    (level * 51) as u8
+}
+
+fn palette_color(index: u8) -> [u8; 3] {
+    let index = index as u32;
+    if index < 216 {
+        [cube_level(index / 36), cube_level(index / 6 % 6), cube_level(index % 6)]
+    } else {
+        let gray = ((index - 216) * 255 / (GRAYS - 1)) as u8;
+        [gray; 3]
+    }
+}
+
+// Closest color of the palette, either from the cube or the grays
+fn nearest_index(color: [u8; 3]) -> u8 {
+    let level = |value: u8| (value as u32 * 5 + 127) / 255;
+    let cube = (level(color[0]) * 36 + level(color[1]) * 6 + level(color[2])) as u8;
+    let average = color.iter().map(|&value| value as u32).sum::<u32>() / 3;
+    let gray = (216 + (average * (GRAYS - 1) + 127) / 255) as u8;
+
+    let distance = |index: u8| {
+        let other = palette_color(index);
+        (0..3).map(|i| (color[i] as i32 - other[i] as i32).pow(2)).sum::<i32>()
+    };
+    if distance(gray) < distance(cube) {
+        gray
+    } else {
+        cube
+    }
+}
+
+// Packs codes of any length into bytes, lowest bits first as GIF expects
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u32,
+    bits: u8,
+}
+
+impl BitWriter {
+    fn write(&mut self, code: u16, size: u8) {
+        self.buffer |= (code as u32) << self.bits;
+        self.bits += size;
+        while self.bits >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.bits -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.bits > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+        self.bytes
+    }
+}
+
+// Compresses the palette indices of a frame, each new code stands for a known run of pixels
+// followed by one more, and codes grow a bit longer whenever the dictionary outgrows them
+fn lzw_encode(pixels: &[u8]) -> Vec<u8> {
+    let clear = 1u16 << MIN_CODE_SIZE;
+    let end = clear + 1;
+
+    let mut writer = BitWriter::default();
+    let mut dictionary: HashMap<(u16, u8), u16> = HashMap::new();
+    let mut next_code = end + 1;
+    let mut code_size = MIN_CODE_SIZE + 1;
+    writer.write(clear, code_size);
+
+    let Some((&first, rest)) = pixels.split_first() else {
+        writer.write(end, code_size);
+        return writer.finish();
+    };
+
+    let mut run = first as u16;
+    for &pixel in rest {
+        if let Some(&code) = dictionary.get(&(run, pixel)) {
+            run = code;
+            continue;
+        }
+
+        writer.write(run, code_size);
+        if next_code < MAX_CODES {
+            dictionary.insert((run, pixel), next_code);
+            next_code += 1;
+            // The decoder adds its entries a code later, so it switches sizes one code after
+            if next_code > 1 << code_size && code_size < 12 {
+                code_size += 1;
+            }
+        } else {
+            writer.write(clear, code_size);
+            dictionary.clear();
+            next_code = end + 1;
+            code_size = MIN_CODE_SIZE + 1;
+        }
+        run = pixel as u16;
+    }
+
+    writer.write(run, code_size);
+    writer.write(end, code_size);
+    writer.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::{Solver, Step};
+    use crate::sudoku::Sudoku;
+
+    // Counts the image descriptors by walking the blocks after the header and palette
+    fn count_frames(bytes: &[u8]) -> usize {
+        let skip_sub_blocks = |mut i: usize| {
+            while bytes[i] != 0 {
+                i += bytes[i] as usize + 1;
+            }
+            i + 1
+        };
+
+        let mut frames = 0;
+        let mut i = 13 + 256 * 3;
+        loop {
+            match bytes[i] {
+                0x21 => i = skip_sub_blocks(i + 2),
+                0x2c => {
+                    frames += 1;
+                    i = skip_sub_blocks(i + 11);
+                }
+                0x3b => return frames,
+                byte => panic!("unexpected block {:#x} at {}", byte, i),
+            }
+        }
+    }
+
+    #[test]
+    fn records_a_frame_per_step() {
+        let mut solver = Solver::with_seed(Sudoku::sample("sudoku1").unwrap(), 0);
+        let dim = solver.board().dim();
+        let mut gif = Gif::new(dim as u16, dim as u16, 10);
+        loop {
+            // A pixel per cell, white once it's filled
+            let rgb: Vec<u8> = (0..dim * dim)
+                .flat_map(|i| [if solver.board().certain_value(i % dim, i / dim).is_some() { 255 } else { 0 }; 3])
+                .collect();
+            gif.add_frame(&rgb, dim * 3);
+            if solver.step() == Step::Complete {
+                break;
+            }
+        }
+
+        let bytes = gif.encode();
+        assert!(bytes.starts_with(b"GIF89a"));
+        assert!(gif.frame_count() > 1);
+        assert_eq!(count_frames(&bytes), gif.frame_count());
+    }
+}
@@ -0,0 +1,109 @@
+use std::fs;
+use std::path::Path;
+
+// Whether the path has to be expanded, only `*` and `?` are understood
+pub fn is_pattern(path: &str) -> bool {
+    path.contains(['*', '?'])
+}
+
+// Every existing path matching the pattern, sorted, wildcards never match across a `/`
+// and hidden files are only matched by a part that starts with `.` itself
+pub fn expand(pattern: &str) -> Result<Vec<String>, String> {
+    let mut paths = vec![if pattern.starts_with('/') { "/".to_string() } else { String::new() }];
+
+    for part in pattern.split('/').filter(|part| !part.is_empty()) {
+        let mut next = vec![];
+
+        for path in &paths {
+            if !is_pattern(part) {
+                next.push(format!("{}{}", path, part));
+                continue;
+            }
+
+            let dir = if path.is_empty() { "." } else { path.as_str() };
+            // Directories that can't be read just don't match anything
+            let Ok(entries) = fs::read_dir(dir) else { continue };
+
+            for entry in entries.flatten() {
+                let name = entry.file_name().to_string_lossy().into_owned();
+                if (!name.starts_with('.') || part.starts_with('.')) && matches(part, &name) {
+                    next.push(format!("{}{}", path, name));
+                }
+            }
+        }
+
+        paths = next.into_iter().map(|path| format!("{}/", path)).collect();
+    }
+
+    let mut matched: Vec<String> = paths
+        .into_iter()
+        .map(|path| path.trim_end_matches('/').to_string())
+        .filter(|path| !path.is_empty() && Path::new(path).exists())
+        .collect();
+    matched.sort();
+
+    if matched.is_empty() {
+        return Err(format!("no files match `{}`", pattern));
+    }
+    Ok(matched)
+}
+
+// `*` matches any run of characters and `?` exactly one
+fn matches(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // Where the last `*` was and how much of the name it has taken so far
+    let mut star: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                // Let the `*` take one more character and try again from there
+                Some((star_p, star_n)) => {
+                    star = Some((star_p, star_n + 1));
+                    p = star_p + 1;
+                    n = star_n + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expands_patterns_in_a_directory() {
+        let dir = std::env::temp_dir().join(format!("sudoku-glob-{}", std::process::id()));
+        fs::create_dir_all(dir.join("more")).unwrap();
+        for name in ["sudoku1.txt", "sudoku2.txt", "sudoku10.txt", "sudoku1.ss", ".hidden.txt", "more/sudoku3.txt"] {
+            fs::write(dir.join(name), "").unwrap();
+        }
+        let dir = dir.to_str().unwrap();
+        let expand_in = |pattern: &str| expand(&format!("{}/{}", dir, pattern)).map(|paths| {
+            paths.into_iter().map(|path| path.trim_start_matches(dir).to_string()).collect::<Vec<_>>()
+        });
+
+        assert_eq!(expand_in("*.txt").unwrap(), ["/sudoku1.txt", "/sudoku10.txt", "/sudoku2.txt"]);
+        assert_eq!(expand_in("sudoku?.txt").unwrap(), ["/sudoku1.txt", "/sudoku2.txt"]);
+        assert_eq!(expand_in("sudoku1.*").unwrap(), ["/sudoku1.ss", "/sudoku1.txt"]);
+        assert_eq!(expand_in(".*.txt").unwrap(), ["/.hidden.txt"]);
+        assert_eq!(expand_in("*/*.txt").unwrap(), ["/more/sudoku3.txt"]);
+        assert!(expand_in("*.json").is_err());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
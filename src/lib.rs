@@ -0,0 +1,13 @@
+pub mod candidates;
+pub mod challenge;
+pub mod error;
+pub mod formats;
+pub mod gif;
+pub mod glob;
+pub mod json;
+pub mod sandwich;
+pub mod script;
+pub mod solver;
+pub mod sudoku;
+pub mod thermo;
+pub mod trace;
@@ -1,25 +1,266 @@
-use sdl2;
 use sdl2::event::Event;
-use sdl2::keyboard::Keycode;
-use sdl2::pixels::Color;
+use sdl2::keyboard::{Keycode, Mod};
+use sdl2::mouse::MouseWheelDirection;
+use sdl2::pixels::{Color, PixelFormatEnum};
 use sdl2::rect::Rect;
-use sdl2::render::{Canvas, TextureCreator};
-use sdl2::rwops::RWops;
+use sdl2::surface::{Surface, SurfaceRef};
+use sdl2::render::{BlendMode, Canvas, RenderTarget, TextureCreator};
 use sdl2::ttf;
-use sdl2::ttf::Font;
-use sdl2::video::{Window, WindowContext};
-use std::{env, process};
+use sdl2::ttf::Sdl2TtfContext;
+use sdl2::video::Window;
+use sdl2::{Sdl, VideoSubsystem};
+use std::time::{Duration, Instant};
+use std::{env, fs, process};
 
-mod sudoku;
-use sudoku::*;
+use sudoku_solver::challenge::{self, Challenge};
+use sudoku_solver::formats;
+use sudoku_solver::gif::Gif;
+use sudoku_solver::solver::{Solver, Step};
+use sudoku_solver::sudoku::*;
+
+mod cli;
+use cli::{CliError, Mode, Options};
+
+mod text;
+use text::{draw_text, Typeface};
 
 const COLOR_STATIC: Color = Color::RGB(29, 32, 33);
 const COLOR_CERTAIN: Color = Color::RGB(0, 131, 176);
+const COLOR_PLAYER: Color = Color::RGB(175, 58, 3);
 const COLOR_UNCERTAIN: Color = Color::RGB(81, 132, 113);
 const COLOR_BACKGROUD: Color = Color::WHITE;
+const COLOR_BANNER: Color = Color::RGB(235, 219, 178);
+const COLOR_SELECTED: Color = Color::RGB(213, 196, 161);
+const COLOR_REVEALED: Color = Color::RGB(143, 63, 113);
+const COLOR_CROSSHAIR: Color = Color::RGBA(213, 196, 161, 90);
+const COLOR_QUADRANT: Color = Color::RGB(242, 236, 221);
+const COLOR_SOLVABLE: Color = Color::RGB(121, 116, 14);
+const COLOR_CHANGED: Color = Color::RGB(250, 240, 200);
+const COLOR_STUCK: Color = Color::RGB(204, 36, 29);
+const COLOR_FOCUSED: Color = Color::RGB(184, 216, 202);
+const COLOR_SAME_DIGIT: Color = Color::RGB(225, 232, 240);
+const COLOR_SHADED_BOX: Color = Color::RGB(246, 246, 246);
+const COLOR_WINDOKU: Color = Color::RGB(228, 238, 222);
+const COLOR_THERMO: Color = Color::RGB(213, 213, 213);
+const COLOR_HEAT: Color = Color::RGB(250, 189, 47);
+const COLOR_EASY: Color = Color::RGB(152, 151, 26);
+const COLOR_MEDIUM: Color = Color::RGB(215, 153, 33);
+const COLOR_HARD: Color = Color::RGB(214, 93, 14);
+const COLOR_EXPERT: Color = Color::RGB(157, 0, 6);
+const COLOR_SWEEP: Color = Color::RGB(189, 214, 235);
+const COLOR_FLASH: Color = Color::RGB(184, 187, 38);
 
 const WINDOW_DIM: u32 = 900;
-const FONT_TFF_BYTES: &[u8] = include_bytes!("../fnt/Iosevka.ttf");
+const PANEL_WIDTH: u32 = 180;
+const PANEL_FONT_SIZE: u16 = 24;
+// Bottom of the panel kept for the status lines, the value counts are spread over the rest
+const STATUS_LINES: u32 = 4;
+const STATUS_HEIGHT: u32 = PANEL_FONT_SIZE as u32 * (STATUS_LINES + 1);
+// How long each cell stays highlighted while `--animate-sweep` works out its candidates
+const SWEEP_INTERVAL: Duration = Duration::from_millis(15);
+// How long wrong numbers stay marked after asking for them
+const MISTAKES_DURATION: Duration = Duration::from_millis(1500);
+// How long each step of `--gif` is shown, in hundredths of a second
+const GIF_FRAME_DELAY: u16 = 10;
+// How long the board flashes once it gets completed
+const FLASH_DURATION: Duration = Duration::from_millis(600);
+
+struct Viewport {
+    board_dim: usize,
+    // Number of times the board is magnified, 1 shows the whole board
+    zoom: usize,
+    // Top-left visible cell
+    x: usize,
+    y: usize,
+}
+
+impl Viewport {
+    const MAX_ZOOM: usize = 4;
+
+    fn new(board_dim: usize) -> Self {
+        Self { board_dim, zoom: 1, x: 0, y: 0 }
+    }
+
+    fn visible_cells(&self) -> usize {
+        self.board_dim.div_ceil(self.zoom)
+    }
+
+    fn field_dim(&self) -> u32 {
+        WINDOW_DIM / self.visible_cells() as u32
+    }
+
+    fn zoom_by(&mut self, delta: isize) {
+        self.zoom = self.zoom.saturating_add_signed(delta).clamp(1, Self::MAX_ZOOM);
+        self.clamp_pan();
+    }
+
+    fn pan_by(&mut self, dx: isize, dy: isize) {
+        self.x = self.x.saturating_add_signed(dx);
+        self.y = self.y.saturating_add_signed(dy);
+        self.clamp_pan();
+    }
+
+    fn clamp_pan(&mut self) {
+        let max = self.board_dim - self.visible_cells();
+        self.x = self.x.min(max);
+        self.y = self.y.min(max);
+    }
+
+    // Pans just enough for the cell to be visible
+    fn scroll_to(&mut self, x: usize, y: usize) {
+        let visible = self.visible_cells();
+        self.x = self.x.min(x).max((x + 1).saturating_sub(visible));
+        self.y = self.y.min(y).max((y + 1).saturating_sub(visible));
+    }
+
+    fn is_visible(&self, x: usize, y: usize) -> bool {
+        let visible = self.visible_cells();
+        (self.x..self.x + visible).contains(&x) && (self.y..self.y + visible).contains(&y)
+    }
+
+    fn cell_at(&self, pos: (i32, i32)) -> Option<(usize, usize)> {
+        let field_dim = self.field_dim() as i32;
+        if pos.0 < 0 || pos.1 < 0 || pos.0 >= WINDOW_DIM as i32 || pos.1 >= WINDOW_DIM as i32 {
+            return None;
+        }
+
+        let x = self.x + (pos.0 / field_dim) as usize;
+        let y = self.y + (pos.1 / field_dim) as usize;
+        (x < self.board_dim && y < self.board_dim).then_some((x, y))
+    }
+
+    // Top-left corner of the cell on screen
+    fn cell_pos(&self, x: usize, y: usize) -> (u32, u32) {
+        let field_dim = self.field_dim();
+        ((x - self.x) as u32 * field_dim, (y - self.y) as u32 * field_dim)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CandidateLayout {
+    // Every number keeps its own spot in the cell, like pencil marks
+    Fixed,
+    // Candidates fill the cell in order, closing the gaps left by eliminated ones
+    Packed,
+}
+
+impl CandidateLayout {
+    fn toggled(self) -> Self {
+        match self {
+            CandidateLayout::Fixed => CandidateLayout::Packed,
+            CandidateLayout::Packed => CandidateLayout::Fixed,
+        }
+    }
+
+    // Center of the `index`th number of the cell, relative to its top-left corner
+    fn offset(self, board: &Sudoku, cell: &Cell, index: usize, num: u32, field_dim: u32) -> (u32, u32) {
+        let nums_len = cell.value().as_vec().len();
+
+        let (cols_amount, rows_amount, slot) = match self {
+            _ if cell.value().is_certain() => (1, 1, 0),
+            // Laid out like the quadrants of the board, 1 top-left and the highest number bottom-right
+            CandidateLayout::Fixed => (board.box_cols() as u32, board.box_rows() as u32, num - 1),
+            CandidateLayout::Packed if nums_len >= 7 => (3, 3, index as u32),
+            CandidateLayout::Packed if nums_len >= 5 => (3, 2, index as u32),
+            CandidateLayout::Packed if nums_len >= 3 => (2, 2, index as u32),
+            CandidateLayout::Packed if nums_len == 2 => (2, 1, index as u32),
+            CandidateLayout::Packed => (1, 1, index as u32),
+        };
+
+        let xspace = field_dim / cols_amount;
+        let yspace = field_dim / rows_amount;
+        (xspace / 2 + xspace * (slot % cols_amount), yspace / 2 + yspace * (slot / cols_amount))
+    }
+}
+
+#[derive(Default)]
+struct SolveTimer {
+    started: Option<Instant>,
+    // Frozen once the board gets completed
+    finished: Option<Duration>,
+}
+
+impl SolveTimer {
+    fn start(&mut self) {
+        if self.started.is_none() {
+            self.started = Some(Instant::now());
+        }
+    }
+
+    fn stop(&mut self) {
+        if let (Some(started), None) = (self.started, self.finished) {
+            self.finished = Some(started.elapsed());
+        }
+    }
+
+    fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+// Tells when the board goes from incomplete to complete, however it got there, the board
+// staying complete afterwards or starting out complete doesn't count
+struct CompletionWatch {
+    was_complete: bool,
+}
+
+impl CompletionWatch {
+    fn new(complete: bool) -> Self {
+        Self { was_complete: complete }
+    }
+
+    // True only for the first frame the board is complete
+    fn update(&mut self, complete: bool) -> bool {
+        let completed = complete && !self.was_complete;
+        self.was_complete = complete;
+        completed
+    }
+}
+
+// Step at which every filled cell was last filled by the solver, for drawing the solve as a wave
+struct SolvedAt {
+    cells: Vec<Option<usize>>,
+    dim: usize,
+    step: usize,
+}
+
+impl SolvedAt {
+    // Steps it takes for a freshly filled cell to fade back to the background
+    const FADE_STEPS: usize = 20;
+
+    fn new(dim: usize) -> Self {
+        Self { cells: vec![None; dim * dim], dim, step: 0 }
+    }
+
+    // Called after every step, cells emptied by backtracking lose their timestamp
+    fn record(&mut self, board: &Sudoku) {
+        self.step += 1;
+        for (y, row) in board.rows().enumerate() {
+            for (x, cell) in row.iter().enumerate() {
+                let solved_at = &mut self.cells[y * self.dim + x];
+                if cell.is_static() || !cell.value().is_certain() {
+                    *solved_at = None;
+                } else if solved_at.is_none() {
+                    *solved_at = Some(self.step);
+                }
+            }
+        }
+    }
+
+    // From 1 for a cell filled by the last step down to 0 once it's faded out
+    fn heat(&self, x: usize, y: usize) -> f32 {
+        match self.cells[y * self.dim + x] {
+            Some(solved_at) => 1.0 - (self.step - solved_at).min(Self::FADE_STEPS) as f32 / Self::FADE_STEPS as f32,
+            None => 0.0,
+        }
+    }
+}
+
+// Color between the background at 0 and `color` at 1
+fn fade(color: Color, amount: f32) -> Color {
+    let mix = |from: u8, to: u8| (from as f32 + (to as f32 - from as f32) * amount) as u8;
+    Color::RGB(mix(COLOR_BACKGROUD.r, color.r), mix(COLOR_BACKGROUD.g, color.g), mix(COLOR_BACKGROUD.b, color.b))
+}
 
 macro_rules! point {
     ($x: expr, $y: expr) => {
@@ -27,32 +268,281 @@ macro_rules! point {
     };
 }
 
-fn draw_text(
-    canvas: &mut Canvas<Window>,
-    texture_creator: &TextureCreator<WindowContext>,
-    font: &Font,
+fn draw_banner<T: RenderTarget, C>(
+    canvas: &mut Canvas<T>,
+    texture_creator: &TextureCreator<C>,
+    font: &Typeface,
     text: &str,
-    pos: (i32, i32),
-    color: Color,
+    center: (i32, i32),
 ) -> Result<(), String> {
-    let surface = font
-        .render(text)
-        .blended(color)
-        .map_err(|e| e.to_string())?;
+    let (w, h) = font.size_of(text)?;
+    let padding = h / 2;
 
-    let texture = texture_creator
-        .create_texture_from_surface(&surface)
-        .map_err(|e| e.to_string())?;
+    canvas.set_draw_color(COLOR_BANNER);
+    canvas.fill_rect(Rect::from_center(center, w + padding * 2, h + padding * 2))?;
+    draw_text(canvas, texture_creator, font, text, center, COLOR_STATIC)?;
+    canvas.set_draw_color(COLOR_STATIC);
+    Ok(())
+}
+
+fn keycode_digit(keycode: Keycode) -> Option<u32> {
+    let digit = match keycode {
+        Keycode::Num1 | Keycode::Kp1 => 1,
+        Keycode::Num2 | Keycode::Kp2 => 2,
+        Keycode::Num3 | Keycode::Kp3 => 3,
+        Keycode::Num4 | Keycode::Kp4 => 4,
+        Keycode::Num5 | Keycode::Kp5 => 5,
+        Keycode::Num6 | Keycode::Kp6 => 6,
+        Keycode::Num7 | Keycode::Kp7 => 7,
+        Keycode::Num8 | Keycode::Kp8 => 8,
+        Keycode::Num9 | Keycode::Kp9 => 9,
+        _ => return None,
+    };
+    Some(digit)
+}
+
+// Moves the value of the cell up or down by `delta`, wrapping around and skipping
+// numbers that would repeat in one of its units
+fn cycle_value(board: &mut Sudoku, x: usize, y: usize, delta: i32) {
+    if board.get_cell(x, y).is_static() {
+        return;
+    }
+
+    let dim = board.dim() as i32;
+    let mut num = match board.get_cell(x, y).value() {
+        CellValue::Certain(num) => *num as i32,
+        CellValue::Uncertain(_) if delta > 0 => 0,
+        CellValue::Uncertain(_) => dim + 1,
+    };
+
+    for _ in 0..dim {
+        num = (num - 1 + delta).rem_euclid(dim) + 1;
+        if board.can_place(x, y, num as u32) {
+            board.set_value(x, y, Some(num as u32));
+            return;
+        }
+    }
+}
+
+// The empty cell after `from` in reading order, or before it when going `backwards`,
+// wrapping around the edges of the board
+fn next_empty_cell(board: &Sudoku, from: (usize, usize), backwards: bool) -> Option<(usize, usize)> {
+    let index = |(x, y): (usize, usize)| y * board.dim() + x;
+    let empty: Vec<(usize, usize)> = board.empty_cells().collect();
+
+    if backwards {
+        empty.iter().rev().find(|&&cell| index(cell) < index(from)).or(empty.last()).copied()
+    } else {
+        empty.iter().find(|&&cell| index(cell) > index(from)).or(empty.first()).copied()
+    }
+}
+
+// Givens, numbers the solver deduced and numbers entered by hand each get their own color,
+// so a finished board still shows which cells were given
+fn cell_color(cell: &Cell) -> Color {
+    if cell.is_static() {
+        return COLOR_STATIC;
+    }
+    if !cell.value().is_certain() {
+        return COLOR_UNCERTAIN;
+    }
+
+    match cell.placement() {
+        Placement::Solver => COLOR_CERTAIN,
+        Placement::Player => COLOR_PLAYER,
+        Placement::Assist => COLOR_UNCERTAIN,
+        Placement::Revealed => COLOR_REVEALED,
+    }
+}
+
+// A single candidate left is still only a candidate, only certain values are drawn big
+fn cell_font<'a, 'ttf>(frame: &Frame<'a, 'ttf>, cell: &Cell) -> &'a Typeface<'ttf> {
+    if cell.value().is_certain() { frame.font } else { frame.small_font }
+}
+
+// While a digit is focused only that candidate is drawn, certain cells are always drawn
+fn shows_candidate(cell: &Cell, num: u32, focused_digit: Option<u32>) -> bool {
+    cell.value().is_certain() || focused_digit.is_none_or(|digit| digit == num)
+}
+
+// A solution of the givens of a solved board that isn't the board itself, if the puzzle has one
+fn other_solution(solved: &Sudoku) -> Option<Sudoku> {
+    let mut givens = solved.clone();
+    givens.reset_board();
+    givens.solutions(2).into_iter().find(|solution| !solution.diff(solved).is_empty())
+}
+
+// Saves the board and loads it back, so everything entered so far becomes a given
+fn save_and_reopen(solver: &mut Solver, sudoku_file: &str) -> String {
+    if let Err(err) = formats::save(solver.board(), sudoku_file) {
+        return format!("Error: Could not save `{}`: {}", sudoku_file, err);
+    }
+
+    let mut board = match formats::load(sudoku_file) {
+        Ok(v) => v,
+        Err(err) => return format!("Error: Could not reopen `{}`: {}", sudoku_file, err),
+    };
+    // Only the numbers are saved, the extra regions have to be carried over
+    _ = board.set_windoku(solver.board().is_windoku());
+
+    let validation = board.validate_givens();
+    replace_board(solver, board);
+
+    match validation {
+        Ok(()) => format!("Saved `{}`", sudoku_file),
+        Err(err) => format!("Error: {}", err),
+    }
+}
 
-    let (w, h) = surface.rect().size();
-    let target = Rect::new(pos.0 - w as i32 / 2, pos.1 - h as i32 / 2, w, h);
+// Starts solving another board with the same settings
+fn replace_board(solver: &mut Solver, board: Sudoku) {
+    let (propagate, deductions, random) = (solver.propagates(), solver.deductions(), solver.is_random());
+    *solver = Solver::new(board);
+    solver.set_propagate(propagate);
+    solver.set_deductions(deductions);
+    solver.set_random(random);
+}
+
+// A board pasted in, either on a single line like the copied ones or with a row per line
+fn parse_pasted(text: &str) -> Result<Sudoku, String> {
+    let board = if text.trim().contains('\n') {
+        formats::Format::Text.parse(text)?
+    } else {
+        text.parse::<Sudoku>().map_err(|err| err.to_string())?
+    };
+
+    board.validate_givens().map_err(|err| err.to_string())?;
+    Ok(board)
+}
+
+fn load_fonts(ttf_context: Option<&Sdl2TtfContext>, field_dim: u32) -> (Typeface<'_>, Typeface<'_>) {
+    let font = Typeface::load(ttf_context, (field_dim as f32 * 0.4) as u16);
+    let small_font = Typeface::load(ttf_context, (field_dim as f32 * 0.25) as u16);
+    (font, small_font)
+}
+
+// Lists how many times each number is already on the board, next to the grid
+fn draw_value_counts<T: RenderTarget, C>(
+    canvas: &mut Canvas<T>,
+    texture_creator: &TextureCreator<C>,
+    font: &Typeface,
+    board: &Sudoku,
+) -> Result<(), String> {
+    let counts = board.cell_count_by_value();
+
+    for (num, count) in counts.iter().enumerate().skip(1) {
+        let color = if *count >= board.dim() { COLOR_CERTAIN } else { COLOR_STATIC };
+        let pos = value_count_pos(num as u32, board.dim());
+        draw_text(canvas, texture_creator, font, &format!("{}: {}", num, count), pos, color)?;
+    }
+
+    Ok(())
+}
+
+// Center of the count of `num`, the counts take the panel down to the status lines
+fn value_count_pos(num: u32, dim: usize) -> (i32, i32) {
+    let line_height = (WINDOW_DIM - STATUS_HEIGHT) / dim as u32;
+    point!(WINDOW_DIM + PANEL_WIDTH / 2, line_height * (num - 1) + line_height / 2)
+}
+
+// Center of the `i`th status line, stacked from the top of their area under the counts
+fn status_line_pos(i: usize) -> (i32, i32) {
+    point!(WINDOW_DIM + PANEL_WIDTH / 2, WINDOW_DIM - STATUS_HEIGHT + PANEL_FONT_SIZE as u32 * (i as u32 + 1))
+}
+
+// Lines shown under the value counts, only the ones that apply to the frame
+fn status_lines(frame: &Frame, board: &Sudoku) -> Vec<(String, Color)> {
+    let mut lines = vec![];
+    if let Some(queued) = frame.queued {
+        lines.push((format!("Queued: {}", queued), COLOR_STATIC));
+    }
+    if let Some(lives) = frame.lives {
+        lines.push((format!("Lives: {}", lives), COLOR_STATIC));
+    }
+    if let Some(solvable) = frame.solvable {
+        let (text, color) = if solvable { ("Solvable", COLOR_SOLVABLE) } else { ("Dead end", COLOR_STUCK) };
+        lines.push((text.to_string(), color));
+    }
+    if frame.edit_mode {
+        lines.push((format!("Mistakes: {}", board.conflict_count()), COLOR_STATIC));
+    }
+    lines
+}
+
+// A round-ish bulb on the first cell and a tube through the centers of the rest, under the numbers
+fn draw_thermometer<T: RenderTarget>(canvas: &mut Canvas<T>, viewport: &Viewport, path: &[(usize, usize)]) -> Result<(), String> {
+    let field_dim = viewport.field_dim() as i32;
+    let center = |(x, y): (usize, usize)| {
+        let (cell_x, cell_y) = viewport.cell_pos(x, y);
+        (cell_x as i32 + field_dim / 2, cell_y as i32 + field_dim / 2)
+    };
+    let square = |(x, y): (i32, i32), side: i32| Rect::new(x - side / 2, y - side / 2, side as u32, side as u32);
+
+    if let Some(&bulb) = path.first().filter(|&&(x, y)| viewport.is_visible(x, y)) {
+        canvas.fill_rect(square(center(bulb), field_dim * 2 / 3))?;
+    }
 
-    canvas.copy(&texture, None, Some(target))?;
+    // Squares along the way between the centers, so diagonal tubes work the same as straight ones
+    const STEPS: i32 = 8;
+    for pair in path.windows(2).filter(|pair| pair.iter().all(|&(x, y)| viewport.is_visible(x, y))) {
+        let ((from_x, from_y), (to_x, to_y)) = (center(pair[0]), center(pair[1]));
+        for step in 0..=STEPS {
+            let point = (from_x + (to_x - from_x) * step / STEPS, from_y + (to_y - from_y) * step / STEPS);
+            canvas.fill_rect(square(point, field_dim / 3))?;
+        }
+    }
+    Ok(())
+}
+
+// Text and color of the difficulty badge
+fn difficulty_badge(difficulty: Option<Difficulty>) -> (String, Color) {
+    match difficulty {
+        Some(difficulty) => {
+            let color = match difficulty {
+                Difficulty::Easy => COLOR_EASY,
+                Difficulty::Medium => COLOR_MEDIUM,
+                Difficulty::Hard => COLOR_HARD,
+                Difficulty::Expert => COLOR_EXPERT,
+            };
+            (difficulty.name().to_uppercase(), color)
+        }
+        None => ("NO SOLUTION".to_string(), COLOR_STUCK),
+    }
+}
+
+// Pill with the difficulty of the puzzle in the top right corner of the board, or that it has no solution
+fn draw_difficulty_badge<T: RenderTarget, C>(
+    canvas: &mut Canvas<T>,
+    texture_creator: &TextureCreator<C>,
+    font: &Typeface,
+    difficulty: Option<Difficulty>,
+) -> Result<(), String> {
+    let (text, color) = difficulty_badge(difficulty);
+    let (w, h) = font.size_of(&text)?;
+    let padding = h / 4;
+    let center = point!(WINDOW_DIM - w / 2 - padding * 3, h / 2 + padding * 3);
+    canvas.set_draw_color(color);
+    canvas.fill_rect(Rect::from_center(center, w + padding * 2, h + padding * 2))?;
+    draw_text(canvas, texture_creator, font, &text, center, COLOR_BACKGROUD)?;
+    canvas.set_draw_color(COLOR_STATIC);
+    Ok(())
+}
+
+// A cross in the middle of a cell that has no number left that fits
+fn draw_stuck_marker<T: RenderTarget>(canvas: &mut Canvas<T>, center: (i32, i32), size: u32) -> Result<(), String> {
+    let size = size as i32;
+    let (x, y) = center;
+    canvas.set_draw_color(COLOR_STUCK);
+    for i in -1..=1 {
+        canvas.draw_line((x - size + i, y - size), (x + size + i, y + size))?;
+        canvas.draw_line((x - size + i, y + size), (x + size + i, y - size))?;
+    }
+    canvas.set_draw_color(COLOR_STATIC);
     Ok(())
 }
 
-pub fn draw_line_thicc(
-    canvas: &mut Canvas<Window>,
+pub fn draw_line_thicc<T: RenderTarget>(
+    canvas: &mut Canvas<T>,
     start: (i32, i32),
     end: (i32, i32),
     thicc: i32,
@@ -69,142 +559,1164 @@ pub fn draw_line_thicc(
     Ok(())
 }
 
+// Everything a frame shows besides the board itself
+struct Frame<'a, 'ttf> {
+    viewport: &'a Viewport,
+    font: &'a Typeface<'ttf>,
+    small_font: &'a Typeface<'ttf>,
+    panel_font: &'a Typeface<'ttf>,
+    edit_mode: bool,
+    selected: (usize, usize),
+    focused_digit: Option<u32>,
+    layout: CandidateLayout,
+    // Whether every other quadrant gets a light background, like a checkerboard
+    shade_boxes: bool,
+    // Cells that changed with the last step
+    changed: &'a [(usize, usize)],
+    // Cell whose candidates were just worked out by an animated sweep
+    swept: Option<(usize, usize)>,
+    // When set cells are tinted brighter the more recently they were filled
+    heat: Option<&'a SolvedAt>,
+    // How much of the completion flash is left, from 1 right after completing down to 0
+    flash: f32,
+    // Wrong numbers being marked, along with how much of the marking is left like `flash`
+    mistakes: (&'a [(usize, usize)], f32),
+    // Quadrant drawn behind a highlight
+    quadrant: Option<(usize, usize)>,
+    // Number whose certain cells are tinted
+    same_digit: Option<u32>,
+    // Lives left in challenge mode
+    lives: Option<u32>,
+    // Alternatives the solver can still backtrack to
+    queued: Option<usize>,
+    // Summary of the solve shown at the bottom of the board, unless there is a message
+    status: Option<String>,
+    // Rating of the puzzle shown in a corner, `Some(None)` when it has no solution
+    difficulty: Option<Option<Difficulty>>,
+    // Whether the board can still be solved, shown while editing
+    solvable: Option<bool>,
+    banner: Option<String>,
+    message: Option<&'a str>,
+}
+
+fn draw_frame<T: RenderTarget, C>(
+    canvas: &mut Canvas<T>,
+    texture_creator: &TextureCreator<C>,
+    board: &Sudoku,
+    frame: &Frame,
+) -> Result<(), String> {
+    canvas.set_draw_color(COLOR_BACKGROUD);
+    canvas.clear();
+    canvas.set_draw_color(COLOR_STATIC);
+
+    let viewport = frame.viewport;
+    let field_dim = viewport.field_dim();
+
+    if frame.flash > 0.0 {
+        canvas.set_draw_color(fade(COLOR_FLASH, frame.flash));
+        canvas.fill_rect(Rect::new(0, 0, WINDOW_DIM, WINDOW_DIM))?;
+    }
+
+    if frame.shade_boxes {
+        canvas.set_draw_color(COLOR_SHADED_BOX);
+        let (quadrants_x, quadrants_y) = (board.dim() / board.box_cols(), board.dim() / board.box_rows());
+        for quadrant_y in 0..quadrants_y {
+            for quadrant_x in (0..quadrants_x).filter(|quadrant_x| (quadrant_x + quadrant_y) % 2 == 1) {
+                for (x, y) in board.quadrant_coords(quadrant_x, quadrant_y) {
+                    if viewport.is_visible(x, y) {
+                        let (cell_x, cell_y) = viewport.cell_pos(x, y);
+                        canvas.fill_rect(Rect::new(cell_x as i32, cell_y as i32, field_dim, field_dim))?;
+                    }
+                }
+            }
+        }
+        canvas.set_draw_color(COLOR_STATIC);
+    }
+
+    canvas.set_draw_color(COLOR_WINDOKU);
+    for (x, y) in board.extra_region_coords().into_iter().flatten().filter(|&(x, y)| viewport.is_visible(x, y)) {
+        let (cell_x, cell_y) = viewport.cell_pos(x, y);
+        canvas.fill_rect(Rect::new(cell_x as i32, cell_y as i32, field_dim, field_dim))?;
+    }
+
+    canvas.set_draw_color(COLOR_THERMO);
+    for path in board.thermometers().map_or(&[][..], |thermometers| &thermometers.paths) {
+        draw_thermometer(canvas, viewport, path)?;
+    }
+
+    if let Some(solved_at) = frame.heat {
+        for y in 0..board.dim() {
+            for x in (0..board.dim()).filter(|&x| viewport.is_visible(x, y) && solved_at.heat(x, y) > 0.0) {
+                let (cell_x, cell_y) = viewport.cell_pos(x, y);
+                canvas.set_draw_color(fade(COLOR_HEAT, solved_at.heat(x, y)));
+                canvas.fill_rect(Rect::new(cell_x as i32, cell_y as i32, field_dim, field_dim))?;
+            }
+        }
+    }
+
+    canvas.set_draw_color(COLOR_CHANGED);
+    for &(x, y) in frame.changed.iter().filter(|&&(x, y)| viewport.is_visible(x, y)) {
+        let (cell_x, cell_y) = viewport.cell_pos(x, y);
+        canvas.fill_rect(Rect::new(cell_x as i32, cell_y as i32, field_dim, field_dim))?;
+    }
+
+    let (mistakes, mistakes_left) = frame.mistakes;
+    if mistakes_left > 0.0 {
+        canvas.set_draw_color(fade(COLOR_STUCK, mistakes_left));
+        for &(x, y) in mistakes.iter().filter(|&&(x, y)| viewport.is_visible(x, y)) {
+            let (cell_x, cell_y) = viewport.cell_pos(x, y);
+            canvas.fill_rect(Rect::new(cell_x as i32, cell_y as i32, field_dim, field_dim))?;
+        }
+    }
+
+    if let Some((x, y)) = frame.swept.filter(|&(x, y)| viewport.is_visible(x, y)) {
+        let (cell_x, cell_y) = viewport.cell_pos(x, y);
+        canvas.set_draw_color(COLOR_SWEEP);
+        canvas.fill_rect(Rect::new(cell_x as i32, cell_y as i32, field_dim, field_dim))?;
+    }
+    canvas.set_draw_color(COLOR_STATIC);
+
+    if let Some((quadrant_x, quadrant_y)) = frame.quadrant {
+        canvas.set_draw_color(COLOR_QUADRANT);
+        for (x, y) in board.quadrant_coords(quadrant_x, quadrant_y) {
+            if viewport.is_visible(x, y) {
+                let (cell_x, cell_y) = viewport.cell_pos(x, y);
+                canvas.fill_rect(Rect::new(cell_x as i32, cell_y as i32, field_dim, field_dim))?;
+            }
+        }
+        canvas.set_draw_color(COLOR_STATIC);
+    }
+
+    // The row and column of the selected cell are tinted, like a spreadsheet selection
+    if frame.edit_mode {
+        let (x, y) = frame.selected;
+        canvas.set_blend_mode(BlendMode::Blend);
+        canvas.set_draw_color(COLOR_CROSSHAIR);
+        if viewport.is_visible(viewport.x, y) {
+            let (_, cell_y) = viewport.cell_pos(viewport.x, y);
+            canvas.fill_rect(Rect::new(0, cell_y as i32, WINDOW_DIM, field_dim))?;
+        }
+        if viewport.is_visible(x, viewport.y) {
+            let (cell_x, _) = viewport.cell_pos(x, viewport.y);
+            canvas.fill_rect(Rect::new(cell_x as i32, 0, field_dim, WINDOW_DIM))?;
+        }
+        canvas.set_blend_mode(BlendMode::None);
+        canvas.set_draw_color(COLOR_STATIC);
+    }
+
+    if let Some(digit) = frame.same_digit {
+        canvas.set_draw_color(COLOR_SAME_DIGIT);
+        for (x, y) in board.cells_with_value(digit).into_iter().filter(|&(x, y)| viewport.is_visible(x, y)) {
+            let (cell_x, cell_y) = viewport.cell_pos(x, y);
+            canvas.fill_rect(Rect::new(cell_x as i32, cell_y as i32, field_dim, field_dim))?;
+        }
+        canvas.set_draw_color(COLOR_STATIC);
+    }
+
+    if frame.edit_mode && viewport.is_visible(frame.selected.0, frame.selected.1) {
+        let (cell_x, cell_y) = viewport.cell_pos(frame.selected.0, frame.selected.1);
+        canvas.set_draw_color(COLOR_SELECTED);
+        canvas.fill_rect(Rect::new(cell_x as i32, cell_y as i32, field_dim, field_dim))?;
+        canvas.set_draw_color(COLOR_STATIC);
+    }
+
+    if let Some(digit) = frame.focused_digit {
+        canvas.set_draw_color(COLOR_FOCUSED);
+        for y in 0..board.dim() {
+            for x in 0..board.dim() {
+                let value = board.get_cell(x, y).value();
+                if viewport.is_visible(x, y) && !value.is_certain() && value.as_vec().contains(&digit) {
+                    let (cell_x, cell_y) = viewport.cell_pos(x, y);
+                    canvas.fill_rect(Rect::new(cell_x as i32, cell_y as i32, field_dim, field_dim))?;
+                }
+            }
+        }
+        canvas.set_draw_color(COLOR_STATIC);
+    }
+
+    for (y, row) in board.rows().enumerate() {
+        for (x, cell) in row.iter().enumerate() {
+            if !viewport.is_visible(x, y) {
+                continue;
+            }
+
+            if board.is_stuck(x, y) {
+                let (cell_x, cell_y) = viewport.cell_pos(x, y);
+                draw_stuck_marker(canvas, point!(cell_x + field_dim / 2, cell_y + field_dim / 2), field_dim / 6)?;
+                continue;
+            }
+
+            if cell.value().as_vec().is_empty() {
+                continue;
+            }
+
+            let font = cell_font(frame, cell);
+
+            let (cell_x, cell_y) = viewport.cell_pos(x, y);
+
+            for (i, num) in cell.value().as_vec().into_iter().enumerate() {
+                let (offset_x, offset_y) = frame.layout.offset(board, cell, i, num, field_dim);
+                let posx = (cell_x + offset_x) as i32;
+                let posy = (cell_y + offset_y) as i32;
+
+                let color = cell_color(cell);
+
+                // Skipped candidates keep their place so the focused one doesn't move around
+                if shows_candidate(cell, num, frame.focused_digit) {
+                    draw_text(canvas, texture_creator, font, &num.to_string(), (posx, posy), color)?;
+                }
+            }
+        }
+    }
+
+    for i in 1..viewport.visible_cells() as u32 {
+        let pos = i * field_dim;
+        let thicc = if (viewport.x as u32 + i).is_multiple_of(board.box_cols() as u32) { 5 } else { 1 };
+        draw_line_thicc(canvas, point!(pos, 0), point!(pos, WINDOW_DIM), thicc)?;
+
+        let thicc = if (viewport.y as u32 + i).is_multiple_of(board.box_rows() as u32) { 5 } else { 1 };
+        draw_line_thicc(canvas, point!(0, pos), point!(WINDOW_DIM, pos), thicc)?;
+    }
+
+    draw_line_thicc(canvas, point!(WINDOW_DIM, 0), point!(WINDOW_DIM, WINDOW_DIM), 5)?;
+    draw_value_counts(canvas, texture_creator, frame.panel_font, board)?;
+
+    for (i, (text, color)) in status_lines(frame, board).iter().enumerate() {
+        draw_text(canvas, texture_creator, frame.panel_font, text, status_line_pos(i), *color)?;
+    }
+
+    if let Some(difficulty) = frame.difficulty {
+        draw_difficulty_badge(canvas, texture_creator, frame.small_font, difficulty)?;
+    }
+
+    if let Some(banner) = &frame.banner {
+        draw_banner(canvas, texture_creator, frame.font, banner, point!(WINDOW_DIM / 2, WINDOW_DIM / 2))?;
+    }
+
+    if let Some(message) = frame.message.or(frame.status.as_deref()) {
+        let center = point!(WINDOW_DIM / 2, WINDOW_DIM - field_dim / 2);
+        draw_banner(canvas, texture_creator, frame.small_font, message, center)?;
+    }
+
+    Ok(())
+}
+
+// Saves a single frame of the solved board as a BMP image, without opening a window
+fn save_snapshot(options: &Options) -> Result<(), CliError> {
+    let snapshot_file = options.snapshot_file().expect("snapshot mode requires an image file");
+    let board = cli::try_load_board(options)?;
+
+    if board.count_solutions(1) == 0 {
+        return Err(CliError::unsolvable(format!("Sudoku `{}`", options.source_name())));
+    }
+
+    // Always the same seed, so the same puzzle always gives the same image
+    let mut solver = Solver::with_seed(board, 0);
+    options.configure(&mut solver);
+    solver.solve();
+
+    let draw_failed = |err: String| CliError::failed("Could not draw the snapshot", err);
+    let ttf_context = ttf::init().ok();
+    let surface = Surface::new(WINDOW_DIM + PANEL_WIDTH, WINDOW_DIM, PixelFormatEnum::RGB24).map_err(draw_failed)?;
+    let mut canvas = surface.into_canvas().map_err(draw_failed)?;
+    let texture_creator = canvas.texture_creator();
+
+    let viewport = Viewport::new(solver.board().dim());
+    let (font, small_font) = load_fonts(ttf_context.as_ref(), viewport.field_dim());
+    let panel_font = Typeface::load(ttf_context.as_ref(), PANEL_FONT_SIZE);
+
+    let frame = still_frame(&viewport, &font, &small_font, &panel_font, &[]);
+    draw_frame(&mut canvas, &texture_creator, solver.board(), &frame).map_err(draw_failed)?;
+
+    canvas.into_surface().save_bmp(snapshot_file).map_err(|err| CliError::io(format!("Could not write `{}`", snapshot_file), err))
+}
+
+// Solves the puzzle the same way a snapshot does, drawing the board after every step,
+// and saves all of the frames as an animated GIF, the solved board is shown twice as long
+fn save_gif(options: &Options) -> Result<(), CliError> {
+    let gif_file = options.gif_file().expect("gif mode requires an image file");
+    let board = cli::try_load_board(options)?;
+
+    if board.count_solutions(1) == 0 {
+        return Err(CliError::unsolvable(format!("Sudoku `{}`", options.source_name())));
+    }
+
+    let mut solver = Solver::with_seed(board, 0);
+    options.configure(&mut solver);
+
+    let draw_failed = |err: String| CliError::failed("Could not draw the animation", err);
+    let ttf_context = ttf::init().ok();
+    let surface = Surface::new(WINDOW_DIM + PANEL_WIDTH, WINDOW_DIM, PixelFormatEnum::RGB24).map_err(draw_failed)?;
+    let mut canvas = surface.into_canvas().map_err(draw_failed)?;
+    let texture_creator = canvas.texture_creator();
+
+    let viewport = Viewport::new(solver.board().dim());
+    let (font, small_font) = load_fonts(ttf_context.as_ref(), viewport.field_dim());
+    let panel_font = Typeface::load(ttf_context.as_ref(), PANEL_FONT_SIZE);
+
+    let mut gif = Gif::new((WINDOW_DIM + PANEL_WIDTH) as u16, WINDOW_DIM as u16, GIF_FRAME_DELAY);
+    let mut add_frame = |board: &Sudoku, changed: &[(usize, usize)]| {
+        let frame = still_frame(&viewport, &font, &small_font, &panel_font, changed);
+        draw_frame(&mut canvas, &texture_creator, board, &frame).map_err(draw_failed)?;
+        let pixels = Pixels::of(canvas.surface()).map_err(draw_failed)?;
+        gif.add_frame(&pixels.data, pixels.pitch);
+        Ok::<(), CliError>(())
+    };
+
+    add_frame(solver.board(), &[])?;
+    loop {
+        match solver.step() {
+            Step::Collapsed { x, y, .. } => add_frame(solver.board(), &[(x, y)])?,
+            Step::Backtracked => add_frame(solver.board(), &[])?,
+            Step::Complete => break,
+            Step::Unsolvable => return Err(CliError::unsolvable(format!("Sudoku `{}`", options.source_name()))),
+        }
+    }
+    // Deducing can fill in the last numbers without a collapse, and either way the solved board stays up longer
+    add_frame(solver.board(), &[])?;
+
+    if options.is_verbose() {
+        println!("Saved {} frames to `{}`", gif.frame_count(), gif_file);
+    }
+    fs::write(gif_file, gif.encode()).map_err(|err| CliError::io(format!("Could not write `{}`", gif_file), err))
+}
+
+// What a snapshot shows, the board and the panel without anything the player would be doing on top
+fn still_frame<'a, 'ttf>(
+    viewport: &'a Viewport,
+    font: &'a Typeface<'ttf>,
+    small_font: &'a Typeface<'ttf>,
+    panel_font: &'a Typeface<'ttf>,
+    changed: &'a [(usize, usize)],
+) -> Frame<'a, 'ttf> {
+    Frame {
+        viewport,
+        font,
+        small_font,
+        panel_font,
+        edit_mode: false,
+        selected: (0, 0),
+        focused_digit: None,
+        layout: CandidateLayout::Fixed,
+        shade_boxes: false,
+        changed,
+        swept: None,
+        heat: None,
+        flash: 0.0,
+        mistakes: (&[], 0.0),
+        quadrant: None,
+        same_digit: None,
+        lives: None,
+        queued: None,
+        status: None,
+        difficulty: None,
+        solvable: None,
+        banner: None,
+        message: None,
+    }
+}
+
+// Pixels of a surface copied out as RGB, so they can be looked at without holding its lock
+struct Pixels {
+    data: Vec<u8>,
+    pitch: usize,
+}
+
+impl Pixels {
+    fn of(surface: &SurfaceRef) -> Result<Self, String> {
+        let surface = surface.convert_format(PixelFormatEnum::RGB24)?;
+        let pitch = surface.pitch() as usize;
+        let data = surface.with_lock(|pixels| pixels.to_vec());
+        Ok(Self { data, pitch })
+    }
+
+    fn color_at(&self, x: u32, y: u32) -> Color {
+        let i = y as usize * self.pitch + x as usize * 3;
+        Color::RGB(self.data[i], self.data[i + 1], self.data[i + 2])
+    }
+
+    // Whether the pixel is part of a digit rather than the cell behind it, going by how dark it is
+    // since the cells can be tinted by the highlights and thermometers, which are all much lighter
+    fn is_ink(&self, x: u32, y: u32) -> bool {
+        let luminance = |color: Color| (299 * color.r as u32 + 587 * color.g as u32 + 114 * color.b as u32) / 1000;
+        luminance(self.color_at(x, y)) < (luminance(COLOR_STATIC) + luminance(COLOR_BACKGROUD)) / 2
+    }
+}
+
+// Recovers the puzzle from an image saved by `--snapshot`, by comparing every cell with the digits
+// rendered by the same fonts, the cells drawn in the color of the givens are the puzzle
+fn load_snapshot(surface: &Surface) -> Result<Sudoku, String> {
+    if surface.size() != (WINDOW_DIM + PANEL_WIDTH, WINDOW_DIM) {
+        return Err("the image is not the size of a snapshot".to_string());
+    }
+    let pixels = Pixels::of(surface)?;
+
+    // Grid lines are the only columns drawn from top to bottom, the right border aside
+    let is_line = |x: u32| (0..WINDOW_DIM).all(|y| pixels.color_at(x, y) == COLOR_STATIC);
+    let lines = (1..WINDOW_DIM - 3).filter(|&x| is_line(x) && !is_line(x - 1)).count();
+    let dim = lines + 1;
+    if Sudoku::box_shape(dim).0 == 1 {
+        return Err("could not find the grid of the board".to_string());
+    }
+
+    let ttf_context = ttf::init().ok();
+    let viewport = Viewport::new(dim);
+    let field_dim = viewport.field_dim();
+    let (font, _) = load_fonts(ttf_context.as_ref(), field_dim);
+    let blank_board = Sudoku::empty_with_dim(dim);
+
+    let mut templates = vec![];
+    for num in 1..=dim as u32 {
+        let mut canvas = Surface::new(field_dim, field_dim, PixelFormatEnum::RGB24)?.into_canvas()?;
+        let texture_creator = canvas.texture_creator();
+        canvas.set_draw_color(COLOR_BACKGROUD);
+        canvas.clear();
+
+        let cell = Cell::new(CellValue::Certain(num), true);
+        let (offset_x, offset_y) = CandidateLayout::Fixed.offset(&blank_board, &cell, 0, num, field_dim);
+        draw_text(&mut canvas, &texture_creator, &font, &num.to_string(), point!(offset_x, offset_y), COLOR_STATIC)?;
+        templates.push(Pixels::of(&canvas.into_surface())?);
+    }
+
+    // Far enough from the edges of the cell to stay clear of the thick grid lines
+    let inset = 3;
+    let mut rows = vec![];
+
+    for y in 0..dim {
+        let mut row = String::new();
+
+        for x in 0..dim {
+            let (cell_x, cell_y) = viewport.cell_pos(x, y);
+            let area = || (inset..field_dim - inset).flat_map(|dy| (inset..field_dim - inset).map(move |dx| (dx, dy)));
+
+            let given = area().filter(|&(dx, dy)| pixels.color_at(cell_x + dx, cell_y + dy) == COLOR_STATIC).count();
+            let filled = area().filter(|&(dx, dy)| pixels.color_at(cell_x + dx, cell_y + dy) == COLOR_CERTAIN).count();
+            if given == 0 || given < filled {
+                row.push('.');
+                continue;
+            }
+
+            let mismatches = |template: &Pixels| {
+                area()
+                    .filter(|&(dx, dy)| pixels.is_ink(cell_x + dx, cell_y + dy) != template.is_ink(dx, dy))
+                    .count()
+            };
+            let best = (0..templates.len()).min_by_key(|&i| mismatches(&templates[i])).expect("boards have digits");
+            row.push(std::char::from_digit(best as u32 + 1, 36).expect("board sizes fit in base 36"));
+        }
+
+        rows.push(row);
+    }
+
+    Ok(Sudoku::from_text(&rows.join("\n")))
+}
+
+fn print_snapshot_board(options: &Options) -> Result<(), CliError> {
+    let image_file = options.snapshot_file().expect("reading a snapshot requires an image file");
+    let context = || format!("Could not read snapshot `{}`", image_file);
+
+    let surface = Surface::load_bmp(image_file).map_err(|err| CliError::io(context(), err))?;
+    let board = load_snapshot(&surface).map_err(|err| CliError::bad_input(context(), err))?;
+    print!("{}", board);
+    Ok(())
+}
+
 fn main() -> Result<(), String> {
-    let mut args = env::args().skip(1);
-    let sudoku_file = match args.next() {
+    let options = Options::from_args(env::args().skip(1)).unwrap_or_else(|message| CliError::bad_input("Invalid arguments", message).exit());
+
+    // Every mode without a window reports its errors the same way, with an exit code for each kind of error
+    let result = match options.mode {
+        Mode::Solve => cli::run_solve(&options),
+        Mode::BenchCandidates => cli::run_bench_candidates(&options),
+        Mode::Replay => cli::run_replay(&options),
+        Mode::Convert => cli::run_convert(&options),
+        Mode::Script => cli::run_script(&options),
+        Mode::Generate => {
+            cli::run_generate(&options);
+            Ok(())
+        }
+        Mode::Snapshot => save_snapshot(&options),
+        Mode::Gif => save_gif(&options),
+        Mode::ReadSnapshot => print_snapshot_board(&options),
+        Mode::DumpCandidates => cli::run_dump_candidates(&options),
+        Mode::Stress => cli::run_stress(&options),
+        Mode::Interactive => return run_interactive(&options),
+    };
+    if let Err(err) = result {
+        err.exit();
+    }
+    Ok(())
+}
+
+fn run_interactive(options: &Options) -> Result<(), String> {
+    // Everything that can exit early happens before SDL is initialized, so no subsystem is left half done
+    let mut solver = Solver::new(cli::load_board(options));
+    options.configure(&mut solver);
+
+    let challenge = options.is_challenge().then(|| match Challenge::new(solver.board(), challenge::DEFAULT_LIVES) {
         Some(v) => v,
         None => {
-            eprintln!("Error: Expected sudoku file");
-            process::exit(1);
+            let context = format!("Sudoku `{}`", options.source_name());
+            CliError::bad_input(context, "challenge mode needs a sudoku with a single solution").exit()
         }
-    };
+    });
 
-    let sdl_context = sdl2::init()?;
-    let ttf_context = ttf::init().map_err(|e| e.to_string())?;
+    run_window(options, solver, challenge)
+}
 
+// Everything that fails when there is no display to open a window on
+fn open_window() -> Result<(Sdl, VideoSubsystem, Canvas<Window>), String> {
+    let sdl_context = sdl2::init()?;
     let video_subsystem = sdl_context.video()?;
 
     let window = video_subsystem
-        .window("Sudoku", WINDOW_DIM, WINDOW_DIM)
+        .window("Sudoku", WINDOW_DIM + PANEL_WIDTH, WINDOW_DIM)
         .position_centered()
         .build()
         .map_err(|e| e.to_string())?;
 
-    let mut canvas = window.into_canvas().build().map_err(|e| e.to_string())?;
-    let mut event_pump = sdl_context.event_pump()?;
-
-    let texture_creator = canvas.texture_creator();
-    let field_dim = WINDOW_DIM / Sudoku::BOARD_DIM as u32;
+    let canvas = window.into_canvas().build().map_err(|e| e.to_string())?;
+    Ok((sdl_context, video_subsystem, canvas))
+}
 
-    let initial_board = match Sudoku::from_file(&sudoku_file) {
+fn run_window(options: &Options, mut solver: Solver, mut challenge: Option<Challenge>) -> Result<(), String> {
+    let (sdl_context, video_subsystem, mut canvas) = match open_window() {
         Ok(v) => v,
-        Err(message) => {
-            eprintln!("Error: Could not read file `{}`: {}", sudoku_file, message);
-            process::exit(1);
+        Err(err) => {
+            let err = CliError::failed("Could not open a window, there may be no display available", err);
+            err.report();
+            eprintln!("Note: `--solve` solves the sudoku without a window");
+            process::exit(err.kind().exit_code());
         }
     };
+    // Without TTF support text falls back to segment displays
+    let ttf_context = ttf::init().ok();
+
+    let mut event_pump = sdl_context.event_pump()?;
+
+    let texture_creator = canvas.texture_creator();
 
-    let mut boards = vec![initial_board];
+    let mut timer = SolveTimer::default();
+    let mut show_banner = false;
 
-    let font_size = (field_dim as f32 * 0.4) as u16;
-    let font = ttf_context.load_font_from_rwops(RWops::from_bytes(FONT_TFF_BYTES)?, font_size)?;
+    let mut edit_mode = false;
+    let mut selected: (usize, usize) = (0, 0);
+    let mut message: Option<String> = None;
+    // Digit whose candidates are the only ones shown
+    let mut focused_digit: Option<u32> = None;
+    let mut layout = CandidateLayout::Fixed;
+    let mut highlight_quadrant = false;
+    let mut highlight_same_digit = false;
+    let mut shade_boxes = false;
+    // Solvability of the board with the given hash, only checked again once the board changes
+    let mut solvable: Option<(u64, bool)> = None;
+    let mut changed: Vec<(usize, usize)> = vec![];
+    let mut solved_at = SolvedAt::new(solver.board().dim());
+    let mut show_heat = false;
+    let mut show_status = false;
+    // Whether space first only narrows down the candidates and collapses on the next press
+    let mut split_steps = false;
+    let mut narrowed = false;
+    // Rated once for every puzzle loaded, a full solve is too slow to do every frame
+    let mut difficulty = solver.board().difficulty();
+    // With `--animate-sweep` space starts a sweep and the step itself only happens once it's done
+    let mut sweep: Option<CandidateSweep> = None;
+    let mut swept: Option<(usize, usize)> = None;
+    let mut last_sweep = Instant::now();
+    let mut step_requested = false;
+    // Hash of the board the solver last ran out of options on, stepping stays off until it changes
+    let mut dead_end: Option<u64> = None;
+    let mut completion = CompletionWatch::new(solver.is_complete());
+    let mut flash_started: Option<Instant> = None;
+    let mut mistakes: Vec<(usize, usize)> = vec![];
+    let mut mistakes_shown: Option<Instant> = None;
+    // Completions of the givens found so far, cycled through once the solve is done
+    let mut solutions: Vec<Sudoku> = vec![];
+    let mut mouse_pos = (0, 0);
 
-    let small_font_size = (field_dim as f32 * 0.25) as u16;
-    let small_font = ttf_context.load_font_from_rwops(RWops::from_bytes(FONT_TFF_BYTES)?, small_font_size)?;
+    let mut viewport = Viewport::new(solver.board().dim());
+    let (mut font, mut small_font) = load_fonts(ttf_context.as_ref(), viewport.field_dim());
+    let panel_font = Typeface::load(ttf_context.as_ref(), PANEL_FONT_SIZE);
+    if font.is_fallback() {
+        eprintln!("Warning: Could not load font, falling back to segment displays");
+    }
 
     'gameloop: loop {
         for event in event_pump.poll_iter() {
+            if let Event::KeyDown { .. } = event {
+                message = None;
+            }
+
             match event {
-                Event::KeyDown {keycode: Some(Keycode::R), .. } => {
-                    boards[0].reset_board();
-                    boards.drain(1..);
+                Event::KeyDown { .. } if show_banner => show_banner = false,
+                // Once every life is lost the only way out is starting over
+                Event::KeyDown { keycode, .. }
+                    if challenge.as_ref().is_some_and(Challenge::is_over) && keycode != Some(Keycode::R) => {}
+                Event::KeyDown { keycode: Some(keycode), keymod, .. }
+                    if keymod.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD) && keycode_digit(keycode).is_some() =>
+                {
+                    let digit = keycode_digit(keycode);
+                    focused_digit = if focused_digit == digit { None } else { digit };
+                }
+                Event::KeyDown { keycode: Some(Keycode::C), keymod, .. } if keymod.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD) => {
+                    message = Some(match video_subsystem.clipboard().set_clipboard_text(&solver.board().to_line()) {
+                        Ok(()) => "Copied the board".to_string(),
+                        Err(err) => format!("Error: Could not copy the board: {}", err),
+                    });
+                }
+                Event::KeyDown { keycode: Some(Keycode::V), keymod, .. } if keymod.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD) => {
+                    let clipboard = video_subsystem.clipboard();
+                    let pasted = if challenge.is_some() {
+                        Err("Boards can't be pasted in challenge mode".to_string())
+                    } else if !clipboard.has_clipboard_text() {
+                        Err("The clipboard has no text".to_string())
+                    } else {
+                        clipboard.clipboard_text().and_then(|text| parse_pasted(&text))
+                    };
+
+                    message = Some(match pasted {
+                        Ok(board) => {
+                            viewport = Viewport::new(board.dim());
+                            (font, small_font) = load_fonts(ttf_context.as_ref(), viewport.field_dim());
+                            selected = (0, 0);
+                            changed.clear();
+                            solved_at = SolvedAt::new(board.dim());
+                            timer.reset();
+                            difficulty = board.difficulty();
+                            sweep = None;
+                            replace_board(&mut solver, board);
+                            completion = CompletionWatch::new(solver.is_complete());
+                            "Pasted the board".to_string()
+                        }
+                        Err(err) => format!("Error: {}", err),
+                    });
+                }
+                Event::KeyDown { keycode: Some(Keycode::E), .. } => edit_mode = !edit_mode,
+                Event::KeyDown { keycode: Some(Keycode::L), .. } => layout = layout.toggled(),
+                Event::KeyDown { keycode: Some(Keycode::Q), .. } => highlight_quadrant = !highlight_quadrant,
+                Event::KeyDown { keycode: Some(Keycode::H), .. } => highlight_same_digit = !highlight_same_digit,
+                Event::KeyDown { keycode: Some(Keycode::B), .. } => shade_boxes = !shade_boxes,
+                Event::KeyDown { keycode: Some(Keycode::W), .. } => show_heat = !show_heat,
+                Event::KeyDown { keycode: Some(Keycode::I), .. } => show_status = !show_status,
+                Event::KeyDown { keycode: Some(Keycode::P), .. } => {
+                    split_steps = !split_steps;
+                    narrowed = false;
+                }
+                Event::KeyDown { keycode: Some(Keycode::A), .. } if edit_mode => {
+                    let filled = solver.board_mut().fill_forced();
+                    message = Some(format!("Filled {} forced cells", filled.len()));
+                }
+                Event::KeyDown { keycode: Some(Keycode::M), .. } if edit_mode => {
+                    // Outside of challenge mode the givens have to be solved first
+                    let found = match &challenge {
+                        Some(challenge) => Some(challenge.mistakes(solver.board())),
+                        None => {
+                            let mut givens = solver.board().clone();
+                            givens.reset_board();
+                            (givens.count_solutions(2) == 1)
+                                .then(|| givens.solution())
+                                .flatten()
+                                .map(|solution| solver.board().mistakes(&solution))
+                        }
+                    };
+
+                    message = Some(match found {
+                        Some(found) => {
+                            let text = match found.len() {
+                                0 => "No mistakes so far".to_string(),
+                                1 => "1 number is wrong".to_string(),
+                                count => format!("{} numbers are wrong", count),
+                            };
+                            mistakes = found;
+                            mistakes_shown = Some(Instant::now());
+                            text
+                        }
+                        None => "Error: The board has no unique solution".to_string(),
+                    });
+                }
+                Event::KeyDown { keycode: Some(Keycode::V), .. } if edit_mode => {
+                    let (x, y) = selected;
+                    match solver.board().solved_value(x, y) {
+                        Some(num) => _ = solver.board_mut().set_value(x, y, Some(num)),
+                        None => message = Some("Error: The board has no unique solution".to_string()),
+                    }
+                }
+                Event::KeyDown { keycode: Some(Keycode::G), .. } if edit_mode => {
+                    message = Some(match solver.board_mut().reveal_solution() {
+                        Some(revealed) => format!("Revealed {} cells", revealed),
+                        None => "Error: The board has no solution".to_string(),
+                    });
+                }
+                Event::KeyDown { keycode: Some(Keycode::S), .. } if edit_mode => {
+                    message = Some(match options.sudoku_file() {
+                        Some(sudoku_file) => save_and_reopen(&mut solver, sudoku_file),
+                        None => "Error: Only boards loaded from a file can be saved".to_string(),
+                    });
+                    solved_at = SolvedAt::new(solver.board().dim());
+                    difficulty = solver.board().difficulty();
                 }
                 Event::KeyDown {
-                    keycode: Some(Keycode::Space),
+                    keycode: Some(keycode @ (Keycode::Left | Keycode::Right | Keycode::Up | Keycode::Down)),
+                    ..
+                } if edit_mode => {
+                    let (x, y) = selected;
+                    let last = solver.board().dim() - 1;
+                    selected = match keycode {
+                        Keycode::Left => (x.saturating_sub(1), y),
+                        Keycode::Right => ((x + 1).min(last), y),
+                        Keycode::Up => (x, y.saturating_sub(1)),
+                        _ => (x, (y + 1).min(last)),
+                    };
+                    viewport.scroll_to(selected.0, selected.1);
+                }
+                Event::KeyDown { keycode: Some(Keycode::Tab), keymod, .. } if edit_mode => {
+                    let backwards = keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD);
+                    if let Some(next) = next_empty_cell(solver.board(), selected, backwards) {
+                        selected = next;
+                        viewport.scroll_to(selected.0, selected.1);
+                    }
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::Backspace | Keycode::Delete | Keycode::Num0 | Keycode::Kp0),
                     ..
-                } if !boards[0].complete() => {
-                    boards[0].update_possible_values();
-                    let (x, y) = boards[0].find_less_entropy();
-
-                    match boards[0].collapse_cell(x, y) {
-                        Ok(other_possibilities) => {
-                            for board in other_possibilities {
-                                boards.insert(1, board);
+                } if edit_mode => {
+                    solver.board_mut().set_value(selected.0, selected.1, None);
+                }
+                Event::KeyDown { keycode: Some(keycode), keymod, .. }
+                    if edit_mode && keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD) && keycode_digit(keycode).is_some() =>
+                {
+                    let (x, y) = selected;
+                    let num = keycode_digit(keycode).expect("checked by the guard");
+                    if !solver.board_mut().toggle_candidate(x, y, num) {
+                        message = Some("Error: Only empty cells have candidates".to_string());
+                    }
+                }
+                Event::KeyDown { keycode: Some(keycode), .. } if edit_mode && keycode_digit(keycode).is_some() => {
+                    let (x, y) = selected;
+                    match (&mut challenge, keycode_digit(keycode)) {
+                        (Some(challenge), Some(num)) => {
+                            if challenge.enter(solver.board_mut(), x, y, num) == Some(false) {
+                                message = Some(format!("Wrong! {} lives left", challenge.lives()));
                             }
                         }
-                        Err(_) => _ = boards.remove(0),
+                        (_, num) => _ = solver.board_mut().set_value(x, y, num),
                     }
+                }
+                Event::KeyDown { keycode: Some(Keycode::N), .. } if solver.is_complete() => {
+                    // Only look again once the board isn't one of the known solutions, like after a reset
+                    let current = solutions.iter().position(|solution| solution.diff(solver.board()).is_empty());
+                    let index = match current {
+                        Some(index) => index,
+                        None => {
+                            solutions = vec![solver.board().clone()];
+                            solutions.extend(other_solution(solver.board()));
+                            0
+                        }
+                    };
 
-                    assert!(boards.len() > 0, "It's a bug... No! Feature");
+                    message = Some(if solutions.len() > 1 {
+                        let next = (index + 1) % solutions.len();
+                        changed = solver.board().diff(&solutions[next]);
+                        *solver.board_mut() = solutions[next].clone();
+                        format!("Solution {} of {}, the puzzle isn't unique", next + 1, solutions.len())
+                    } else {
+                        "The puzzle has a single solution".to_string()
+                    });
+                }
+                Event::KeyDown {keycode: Some(Keycode::R), .. } => {
+                    solver.reset();
+                    timer.reset();
+                    changed.clear();
+                    solved_at = SolvedAt::new(solver.board().dim());
+                    narrowed = false;
+                    sweep = None;
+                    if let Some(challenge) = &mut challenge {
+                        challenge.restart();
+                    }
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::Space),
+                    ..
+                } if !solver.is_complete() && sweep.is_none() && dead_end != Some(solver.board().board_hash()) => {
+                    timer.start();
+                    // The collapsing half of a split step has nothing left to work out
+                    if options.animates_sweep() && !narrowed {
+                        sweep = Some(CandidateSweep::new());
+                    } else {
+                        step_requested = true;
+                    }
+                }
+                Event::KeyDown {
+                    keycode: Some(keycode @ (Keycode::Plus | Keycode::Equals | Keycode::KpPlus | Keycode::Minus | Keycode::KpMinus)),
+                    ..
+                } => {
+                    let delta = if matches!(keycode, Keycode::Minus | Keycode::KpMinus) { -1 } else { 1 };
+                    viewport.zoom_by(delta);
+                    (font, small_font) = load_fonts(ttf_context.as_ref(), viewport.field_dim());
+                }
+                Event::KeyDown { keycode: Some(Keycode::Left), .. } => viewport.pan_by(-1, 0),
+                Event::KeyDown { keycode: Some(Keycode::Right), .. } => viewport.pan_by(1, 0),
+                Event::KeyDown { keycode: Some(Keycode::Up), .. } => viewport.pan_by(0, -1),
+                Event::KeyDown { keycode: Some(Keycode::Down), .. } => viewport.pan_by(0, 1),
+                Event::MouseMotion { x, y, .. } => mouse_pos = (x, y),
+                // Cycling through the numbers would find the right one without losing any life
+                Event::MouseWheel { y: scroll, direction, .. } if scroll != 0 && challenge.is_none() => {
+                    let delta = if direction == MouseWheelDirection::Flipped { -scroll.signum() } else { scroll.signum() };
+                    if let Some((x, y)) = viewport.cell_at(mouse_pos) {
+                        if !solver.board().get_cell(x, y).is_static() {
+                            cycle_value(solver.board_mut(), x, y, delta);
+                            selected = (x, y);
+                        }
+                    }
                 }
                 Event::Quit { .. } => break 'gameloop,
                 _ => {}
             }
         }
 
-        canvas.clear();
-        canvas.set_draw_color(COLOR_STATIC);
-
-        for y in 0..Sudoku::BOARD_DIM {
-            for x in 0..Sudoku::BOARD_DIM {
-                let nums_len = boards[0].get_cell(x, y).value().as_vec().len();
-                if nums_len == 0 {
-                    continue;
-                }
-
-                let (cols_amount, rows_amount) = if nums_len >= 7 {
-                    (3, 3)
-                } else if nums_len >= 5 {
-                    (3, 2)
-                } else if nums_len >= 3 {
-                    (2, 2)
-                } else if nums_len == 2 {
-                    (2, 1)
-                } else {
-                    (1, 1)
-                };
-
-                let font = if nums_len == 1 { &font } else { &small_font };
-
-                let xspace = field_dim / cols_amount;
-                let yspace = field_dim / rows_amount;
-
-                let mut xcurr: u32 = 0;
-                let mut ycurr: u32 = 0;
-
-                for num in boards[0].get_cell(x, y).value().as_vec() {
-                    let posx = (x as u32 * field_dim + xspace / 2 + xspace * xcurr) as i32;
-                    let posy = (y as u32 * field_dim + yspace / 2 + yspace * ycurr) as i32;
-
-                    let color = if boards[0].get_cell(x, y).is_static() {
-                        COLOR_STATIC
-                    } else if boards[0].get_cell(x, y).value().is_certain() {
-                        COLOR_CERTAIN
-                    } else {
-                        COLOR_UNCERTAIN
-                    };
-
-                    draw_text(&mut canvas, &texture_creator, font, &num.to_string(), (posx, posy), color)?;
+        if let Some(active) = &mut sweep {
+            if last_sweep.elapsed() >= SWEEP_INTERVAL {
+                last_sweep = Instant::now();
+                swept = solver.sweep_candidates(active);
+                if swept.is_none() {
+                    sweep = None;
+                    step_requested = true;
+                }
+            }
+        } else {
+            swept = None;
+        }
 
-                    xcurr += 1;
-                    if xcurr >= cols_amount {
-                        ycurr += 1;
-                        xcurr = 0;
-                    }
+        if step_requested {
+            step_requested = false;
+            let before = solver.board().clone();
+            if split_steps && !narrowed {
+                solver.narrow();
+                narrowed = true;
+            } else {
+                // Numbers entered by hand can contradict each other, which leaves nothing to collapse
+                if solver.step() == Step::Unsolvable {
+                    dead_end = Some(solver.board().board_hash());
+                    timer.stop();
                 }
+                narrowed = false;
+            }
+            changed = before.diff(solver.board());
+            solved_at.record(solver.board());
+
+            if solver.is_complete() {
+                timer.stop();
+                show_banner = true;
             }
         }
 
-        for i in 1..Sudoku::BOARD_DIM as u32 {
-            let pos = i * field_dim;
-            let thicc = if i % 3 == 0 { 5 } else { 1 };
-            draw_line_thicc(&mut canvas, point!(pos, 0), point!(pos, WINDOW_DIM), thicc)?;
-            draw_line_thicc(&mut canvas, point!(0, pos), point!(WINDOW_DIM, pos), thicc)?;
+        if completion.update(solver.is_complete()) {
+            flash_started = Some(Instant::now());
         }
+        let mistakes_left = mistakes_shown.map_or(0.0, |shown| 1.0 - (shown.elapsed().as_secs_f32() / MISTAKES_DURATION.as_secs_f32()).min(1.0));
+        let flash = flash_started.map_or(0.0, |started| 1.0 - (started.elapsed().as_secs_f32() / FLASH_DURATION.as_secs_f32()).min(1.0));
 
-        canvas.set_draw_color(COLOR_BACKGROUD);
+        let banner = if challenge.as_ref().is_some_and(Challenge::is_over) {
+            Some("Game over! Press r to try again".to_string())
+        } else {
+            show_banner.then(|| format!("Solved! {:.2}s", timer.finished.unwrap_or_default().as_secs_f32()))
+        };
+        if edit_mode {
+            let hash = solver.board().board_hash();
+            if solvable.is_none_or(|(solvable_hash, _)| solvable_hash != hash) {
+                solvable = Some((hash, solver.board().is_solvable()));
+            }
+        }
+
+        // The selected cell while editing, the one under the cursor otherwise
+        let focus = if edit_mode { Some(selected) } else { viewport.cell_at(mouse_pos) };
+        let quadrant = focus.filter(|_| highlight_quadrant).map(|(x, y)| solver.board().box_of(x, y));
+        let same_digit = focus.filter(|_| highlight_same_digit).and_then(|(x, y)| solver.board().certain_value(x, y));
+
+        let frame = Frame {
+            viewport: &viewport,
+            font: &font,
+            small_font: &small_font,
+            panel_font: &panel_font,
+            edit_mode,
+            selected,
+            focused_digit,
+            layout,
+            shade_boxes,
+            changed: &changed,
+            swept,
+            flash,
+            mistakes: (&mistakes, mistakes_left),
+            heat: show_heat.then_some(&solved_at),
+            quadrant,
+            same_digit,
+            lives: challenge.as_ref().map(Challenge::lives),
+            queued: Some(solver.depth()),
+            status: show_status.then(|| solver.status()),
+            difficulty: Some(difficulty),
+            solvable: solvable.filter(|_| edit_mode).map(|(_, solvable)| solvable),
+            banner,
+            message: message.as_deref().or((dead_end == Some(solver.board().board_hash())).then_some("No solution, fix the board or press r")),
+        };
+        draw_frame(&mut canvas, &texture_creator, solver.board(), &frame)?;
         canvas.present();
     }
 
+    // Fonts and textures before the canvas, and the canvas before the contexts it was made from,
+    // some platforms report errors on exit otherwise
+    drop((font, small_font, panel_font));
+    drop(texture_creator);
+    drop(event_pump);
+    drop(canvas);
+    drop(video_subsystem);
+    drop(ttf_context);
+    drop(sdl_context);
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn completion_fires_once() {
+        let mut watch = CompletionWatch::new(false);
+        let fired: Vec<bool> = [false, true, true, true].into_iter().map(|complete| watch.update(complete)).collect();
+        assert_eq!(fired, [false, true, false, false]);
+
+        // Emptying a cell and filling it again completes the board a second time
+        assert!(!watch.update(false));
+        assert!(watch.update(true));
+
+        let mut watch = CompletionWatch::new(true);
+        assert!(!watch.update(true));
+    }
+
+    #[test]
+    fn status_lines_stay_under_the_counts() {
+        for dim in [4, 6, 9, 12, 16, 25] {
+            let last_count = value_count_pos(dim as u32, dim).1 + PANEL_FONT_SIZE as i32 / 2;
+            let first_status = status_line_pos(0).1 - PANEL_FONT_SIZE as i32 / 2;
+            assert!(last_count <= first_status, "{}x{} counts run into the status lines", dim, dim);
+        }
+        let last_status = status_line_pos(STATUS_LINES as usize - 1).1 + PANEL_FONT_SIZE as i32 / 2;
+        assert!(last_status <= WINDOW_DIM as i32);
+    }
+
+    #[test]
+    fn shows_the_queued_alternatives() {
+        let (viewport, font) = (Viewport::new(9), Typeface::Segments(PANEL_FONT_SIZE));
+        let board = Sudoku::sample("sudoku1").unwrap();
+        let mut frame = still_frame(&viewport, &font, &font, &font, &[]);
+        assert!(status_lines(&frame, &board).is_empty());
+
+        frame.queued = Some(3);
+        assert_eq!(status_lines(&frame, &board), [("Queued: 3".to_string(), COLOR_STATIC)]);
+    }
+
+    #[test]
+    fn shows_dead_ends_while_editing() {
+        let (viewport, font) = (Viewport::new(9), Typeface::Segments(PANEL_FONT_SIZE));
+        let mut board = Sudoku::sample("sudoku1").unwrap();
+        let mut frame = still_frame(&viewport, &font, &font, &font, &[]);
+        frame.edit_mode = true;
+        frame.solvable = Some(board.is_solvable());
+        assert_eq!(status_lines(&frame, &board)[0], ("Solvable".to_string(), COLOR_SOLVABLE));
+
+        // A candidate that repeats nothing but isn't in the only solution
+        let answer = board.solution().unwrap().certain_value(2, 0);
+        board.update_possible_values();
+        let wrong = board.get_cell(2, 0).value().as_vec().into_iter().find(|&num| Some(num) != answer).unwrap();
+        board.set_value(2, 0, Some(wrong));
+        assert_eq!(board.conflict_count(), 0);
+        frame.solvable = Some(board.is_solvable());
+        assert_eq!(status_lines(&frame, &board)[0], ("Dead end".to_string(), COLOR_STUCK));
+    }
+
+    #[test]
+    fn parses_pasted_lines() {
+        let board = Sudoku::sample("sudoku1").unwrap();
+        assert_eq!(parse_pasted(&board.to_line()).unwrap().to_string(), board.to_string());
+        assert_eq!(parse_pasted(&board.to_string()).unwrap().to_string(), board.to_string());
+
+        let repeated = format!("55{}", &board.to_line()[2..]);
+        for text in ["", "123", &".".repeat(49), &repeated] {
+            assert!(parse_pasted(text).is_err(), "`{}` was accepted", text);
+        }
+    }
+
+    #[test]
+    fn tinted_cells_are_not_ink() {
+        let colors = [COLOR_STATIC, COLOR_BACKGROUD, COLOR_SELECTED, COLOR_THERMO, COLOR_HEAT, COLOR_SAME_DIGIT, COLOR_WINDOKU];
+        let data = colors.iter().flat_map(|color| [color.r, color.g, color.b]).collect();
+        let pixels = Pixels { data, pitch: colors.len() * 3 };
+        let ink: Vec<bool> = (0..colors.len() as u32).map(|x| pixels.is_ink(x, 0)).collect();
+        assert_eq!(ink, [true, false, false, false, false, false, false]);
+    }
+
+    #[test]
+    fn focusing_a_digit_hides_the_other_candidates() {
+        let mut board = Sudoku::sample("sudoku1").unwrap();
+        board.update_possible_values();
+        let given = board.get_cell(0, 0);
+        let empty = board.get_cell(2, 0);
+
+        assert!(shows_candidate(empty, 1, None));
+        assert!(shows_candidate(empty, 4, Some(4)));
+        assert!(!shows_candidate(empty, 1, Some(4)));
+        assert!(shows_candidate(given, 5, Some(4)));
+    }
+
+    #[test]
+    fn pencil_marks_stay_in_their_spot() {
+        let mut board = Sudoku::empty();
+        board.update_possible_values();
+        let spots: Vec<(u32, u32)> = (1..=9)
+            .map(|num| CandidateLayout::Fixed.offset(&board, board.get_cell(0, 0), num as usize - 1, num, 90))
+            .collect();
+        assert_eq!(spots.iter().collect::<HashSet<_>>().len(), 9);
+        assert_eq!(spots[0], (15, 15));
+        assert_eq!(spots[8], (75, 75));
+
+        // Eliminating candidates moves the packed ones but not the pencil marks
+        board.set_candidates(0, 0, &[2, 5, 8]);
+        let cell = board.get_cell(0, 0);
+        for (index, num) in [2, 5, 8].into_iter().enumerate() {
+            assert_eq!(CandidateLayout::Fixed.offset(&board, cell, index, num, 90), spots[num as usize - 1]);
+        }
+        assert_ne!(CandidateLayout::Packed.offset(&board, cell, 1, 5, 90), spots[4]);
+    }
+
+    #[test]
+    fn snapshots_write_an_image() {
+        let dir = env::temp_dir().join(format!("sudoku-snapshot-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let image = dir.join("sudoku1.bmp");
+        let args = ["--snapshot", image.to_str().unwrap(), "--sample", "sudoku1"];
+
+        save_snapshot(&Options::from_args(args.iter().map(|arg| arg.to_string())).unwrap()).unwrap();
+        assert!(fs::metadata(&image).unwrap().len() > 0);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn single_candidates_are_drawn_small() {
+        let viewport = Viewport::new(9);
+        let (font, small_font) = (Typeface::Segments(40), Typeface::Segments(10));
+        let frame = still_frame(&viewport, &font, &small_font, &font, &[]);
+        let mut board = Sudoku::sample("sudoku1").unwrap();
+        board.set_candidates(2, 0, &[4]);
+        board.set_value(4, 4, Some(5));
+
+        let candidate = board.get_cell(2, 0);
+        let certain = board.get_cell(4, 4);
+        assert_eq!(cell_font(&frame, candidate).size_of("4"), small_font.size_of("4"));
+        assert_eq!(cell_font(&frame, certain).size_of("5"), font.size_of("5"));
+        let field_dim = viewport.field_dim();
+        assert_ne!(frame.layout.offset(&board, candidate, 0, 4, field_dim), frame.layout.offset(&board, certain, 0, 5, field_dim));
+    }
+
+    #[test]
+    fn finds_a_different_solution() {
+        let unique = Sudoku::sample("sudoku1").unwrap().solution().unwrap();
+        assert!(other_solution(&unique).is_none());
+
+        // A single given leaves plenty of ways to fill in the rest
+        let solved = Sudoku::from_text("1...\n....\n....\n....\n").solution().unwrap();
+        let other = other_solution(&solved).unwrap();
+        assert!(other.complete());
+        assert!(!other.diff(&solved).is_empty());
+        assert_eq!(other.certain_value(0, 0), Some(1));
+    }
+
+    #[test]
+    fn reads_back_the_givens_of_snapshots() {
+        let dir = env::temp_dir().join(format!("sudoku-read-snapshot-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        for sample in ["sudoku1", "sudoku8"] {
+            let image = dir.join(format!("{}.bmp", sample));
+            let args = ["--snapshot", image.to_str().unwrap(), "--sample", sample];
+            save_snapshot(&Options::from_args(args.iter().map(|arg| arg.to_string())).unwrap()).unwrap();
+
+            let board = load_snapshot(&Surface::load_bmp(&image).unwrap()).unwrap();
+            assert_eq!(board.to_string(), Sudoku::sample(sample).unwrap().to_string(), "{}", sample);
+        }
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn jumps_to_the_next_empty_cell() {
+        let mut board = Sudoku::sample("sudoku1").unwrap();
+        board.set_value(2, 0, Some(4));
+
+        // Past the givens in the corner and the number entered next to them
+        assert_eq!(next_empty_cell(&board, (0, 0), false), Some((3, 0)));
+        assert_eq!(next_empty_cell(&board, (3, 0), false), Some((5, 0)));
+        assert_eq!(next_empty_cell(&board, (5, 0), true), Some((3, 0)));
+        // Wrapping around both ways
+        assert_eq!(next_empty_cell(&board, (3, 0), true), Some((6, 8)));
+        assert_eq!(next_empty_cell(&board, (6, 8), false), Some((3, 0)));
+
+        assert_eq!(next_empty_cell(&board.solution().unwrap(), (0, 0), false), None);
+    }
+
+    #[test]
+    fn timestamps_follow_the_collapses() {
+        let mut solver = Solver::with_seed(Sudoku::sample("sudoku1").unwrap(), 0);
+        let mut solved_at = SolvedAt::new(9);
+        let mut collapsed = vec![];
+
+        loop {
+            let step = solver.step();
+            solved_at.record(solver.board());
+            match step {
+                Step::Collapsed { x, y, .. } => {
+                    assert_eq!(solved_at.cells[y * 9 + x], Some(solved_at.step));
+                    assert_eq!(solved_at.heat(x, y), 1.0);
+                    collapsed.push((x, y));
+                }
+                Step::Complete => break,
+                step => panic!("an easy puzzle got to {:?}", step),
+            }
+        }
+
+        let stamps: Vec<usize> = collapsed.iter().map(|&(x, y)| solved_at.cells[y * 9 + x].unwrap()).collect();
+        assert!(stamps.windows(2).all(|pair| pair[0] < pair[1]), "{:?}", stamps);
+        assert_eq!(solved_at.cells[0], None);
+    }
+
+    #[test]
+    fn tells_givens_from_solved_cells() {
+        let mut solver = Solver::with_seed(Sudoku::sample("sudoku1").unwrap(), 0);
+        solver.step();
+        let Some(&Step::Collapsed { x, y, .. }) = solver.history().last() else { panic!("the first step didn't collapse") };
+        let board = solver.board();
+        assert_eq!(cell_color(board.get_cell(0, 0)), COLOR_STATIC);
+        assert_eq!(cell_color(board.get_cell(x, y)), COLOR_CERTAIN);
+
+        let mut board = Sudoku::sample("sudoku1").unwrap();
+        board.set_value(2, 0, Some(4));
+        assert_eq!(cell_color(board.get_cell(2, 0)), COLOR_PLAYER);
+        assert_eq!(cell_color(board.get_cell(3, 0)), COLOR_UNCERTAIN);
+        board.reveal_solution();
+        assert_eq!(cell_color(board.get_cell(3, 0)), COLOR_REVEALED);
+        assert_eq!(cell_color(board.get_cell(0, 0)), COLOR_STATIC);
+    }
+
+    #[test]
+    fn badges_rate_the_samples() {
+        let badges: Vec<String> = Sudoku::sample_names()
+            .into_iter()
+            .map(|name| difficulty_badge(Sudoku::sample(name).unwrap().difficulty()).0)
+            .collect();
+        assert_eq!(badges, ["EASY", "MEDIUM", "HARD", "MEDIUM", "EXPERT", "HARD", "EASY", "EASY"]);
+
+        let dead_end = Sudoku::from_text(&format!("12345678.\n........9\n{}", ".........\n".repeat(7)));
+        assert_eq!(difficulty_badge(dead_end.difficulty()), ("NO SOLUTION".to_string(), COLOR_STUCK));
+        assert_eq!(difficulty_badge(Some(Difficulty::Hard)).1, COLOR_HARD);
+    }
+}
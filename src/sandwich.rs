@@ -0,0 +1,149 @@
+// Sandwich clues, the sum of the numbers between the 1 and the highest number of a row or column
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SandwichClues {
+    // `None` for lines without a clue
+    pub rows: Vec<Option<u32>>,
+    pub columns: Vec<Option<u32>>,
+}
+
+impl SandwichClues {
+    // Larger boards have too many ways of filling the space between the 1 and the highest number
+    pub const MAX_DIM: usize = 16;
+
+    // Two lines, the clues of the rows and then the ones of the columns, separated by spaces
+    // with `.` where there is no clue, blank lines and lines starting with `#` are ignored
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let lines: Vec<&str> = text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .collect();
+
+        if lines.len() != 2 {
+            return Err(format!("expected a line of row clues and one of column clues but found {} lines", lines.len()));
+        }
+
+        let parse_line = |line: &str| {
+            line.split_whitespace()
+                .map(|clue| match clue {
+                    "." => Ok(None),
+                    _ => clue.parse().map(Some).map_err(|_| format!("invalid sandwich clue `{}`", clue)),
+                })
+                .collect::<Result<Vec<_>, String>>()
+        };
+
+        let rows = parse_line(lines[0])?;
+        let columns = parse_line(lines[1])?;
+        if rows.len() != columns.len() {
+            return Err(format!("found {} row clues but {} column clues", rows.len(), columns.len()));
+        }
+
+        Ok(Self { rows, columns })
+    }
+
+    pub fn to_text(&self) -> String {
+        let line = |clues: &[Option<u32>]| {
+            let clues: Vec<String> = clues.iter().map(|clue| clue.map_or(".".to_string(), |sum| sum.to_string())).collect();
+            clues.join(" ")
+        };
+        format!("{}\n{}\n", line(&self.rows), line(&self.columns))
+    }
+}
+
+// Sum of the numbers between the 1 and `max`, `None` unless both are in the line once
+pub(crate) fn sandwich_sum(line: &[u32], max: u32) -> Option<u32> {
+    let first = line.iter().position(|&num| num == 1)?;
+    let last = line.iter().position(|&num| num == max)?;
+    let (start, end) = (first.min(last), first.max(last));
+    Some(line[start + 1..end].iter().sum())
+}
+
+// Given the candidates of every cell of a line as bitmasks, with bit `n` set when `n` fits,
+// returns the candidates that are still possible with the clue, all of them zero when none is
+// Every place the 1 and `max` can go is tried, together with every set of numbers adding
+// up to the clue that fits in between, while the rest of the numbers go outside
+pub(crate) fn allowed_in_line(candidates: &[u64], clue: u32, max: u32) -> Vec<u64> {
+    let len = candidates.len();
+    let mut allowed = vec![0; len];
+    // Every number but the 1 and `max`
+    let middle: u64 = (2..max).map(|num| 1u64 << num).sum();
+
+    let fillings: Vec<u64> = subsets(middle).filter(|&subset| mask_sum(subset) == clue).collect();
+
+    for one in (0..len).filter(|&i| candidates[i] & (1 << 1) != 0) {
+        for top in (0..len).filter(|&i| i != one && candidates[i] & (1 << max) != 0) {
+            let (start, end) = (one.min(top), one.max(top));
+
+            for &filling in fillings.iter().filter(|filling| filling.count_ones() as usize == end - start - 1) {
+                // Numbers between the ends have to come from the filling and the rest from outside of it
+                let fits = |i: usize| if (start + 1..end).contains(&i) { filling } else { middle & !filling };
+                let others = (0..len).filter(|&i| i != one && i != top);
+
+                if others.clone().all(|i| candidates[i] & fits(i) != 0) {
+                    allowed[one] |= 1 << 1;
+                    allowed[top] |= 1 << max;
+                    for i in others {
+                        allowed[i] |= candidates[i] & fits(i);
+                    }
+                }
+            }
+        }
+    }
+
+    allowed
+}
+
+// Every subset of the bits of `mask`
+fn subsets(mask: u64) -> impl Iterator<Item = u64> {
+    let mut next = Some(mask);
+    std::iter::from_fn(move || {
+        let subset = next?;
+        next = (subset != 0).then(|| (subset - 1) & mask);
+        Some(subset)
+    })
+}
+
+fn mask_sum(mask: u64) -> u32 {
+    (0..64).filter(|bit| mask & (1 << bit) != 0).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sudoku::Sudoku;
+
+    fn mask(nums: impl IntoIterator<Item = u32>) -> u64 {
+        nums.into_iter().map(|num| 1u64 << num).sum()
+    }
+
+    #[test]
+    fn clues_narrow_down_lines() {
+        let open = vec![mask(1..=9); 9];
+
+        // Only every number from 2 to 8 adds up to 35, so the 1 and the 9 go at the ends
+        let allowed = allowed_in_line(&open, 35, 9);
+        assert_eq!(allowed[0], mask([1, 9]));
+        assert_eq!(allowed[8], mask([1, 9]));
+        assert!(allowed[1..8].iter().all(|&cell| cell == mask(2..=8)));
+
+        // With the 1 first a sum of 2 can only be a 2 and then the 9
+        let mut line = open.clone();
+        line[0] = mask([1]);
+        let allowed = allowed_in_line(&line, 2, 9);
+        assert_eq!(allowed[..3], [mask([1]), mask([2]), mask([9])]);
+        assert!(allowed[3..].iter().all(|&cell| cell == mask(3..=8)));
+
+        assert!(allowed_in_line(&line, 100, 9).iter().all(|&cell| cell == 0));
+    }
+
+    #[test]
+    fn clues_narrow_down_candidates() {
+        let mut board = Sudoku::empty();
+        board.set_sandwich_clues(SandwichClues::parse("35 . . . . . . . .\n. . . . . . . . .\n").unwrap()).unwrap();
+        board.update_possible_values();
+
+        assert_eq!(board.get_cell(0, 0).value().as_vec(), [1, 9]);
+        assert_eq!(board.get_cell(4, 0).value().as_vec(), [2, 3, 4, 5, 6, 7, 8]);
+        assert_eq!(board.get_cell(4, 1).value().as_vec(), (1..=9).collect::<Vec<_>>());
+    }
+}
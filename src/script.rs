@@ -0,0 +1,139 @@
+use std::io::Write;
+
+use crate::formats;
+use crate::solver::{Solver, Step};
+use crate::sudoku::Sudoku;
+
+// One line of a script, mirroring what can be done in the window
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Load(String),
+    Step(usize),
+    Solve,
+    Reset,
+    Collapse { x: usize, y: usize },
+    Save(String),
+    Print,
+}
+
+impl Command {
+    // Blank lines and lines starting with `#` are not commands
+    pub fn parse(line: &str) -> Result<Option<Self>, String> {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            return Ok(None);
+        }
+
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let number = |word: &str| word.parse::<usize>().map_err(|_| format!("expected a number but found `{}`", word));
+
+        let command = match words.as_slice() {
+            ["load", path] => Command::Load(path.to_string()),
+            ["step"] => Command::Step(1),
+            ["step", count] => Command::Step(number(count)?),
+            ["solve"] => Command::Solve,
+            ["reset"] => Command::Reset,
+            ["collapse", x, y] => Command::Collapse { x: number(x)?, y: number(y)? },
+            ["save", path] => Command::Save(path.to_string()),
+            ["print"] => Command::Print,
+            _ => return Err(format!("unknown command `{}`", line)),
+        };
+        Ok(Some(command))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Script {
+    // Each command along with the line it came from
+    commands: Vec<(usize, Command)>,
+}
+
+impl Script {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut commands = vec![];
+        for (i, line) in text.lines().enumerate() {
+            match Command::parse(line) {
+                Ok(Some(command)) => commands.push((i + 1, command)),
+                Ok(None) => {}
+                Err(message) => return Err(format!("line {}: {}", i + 1, message)),
+            }
+        }
+        Ok(Self { commands })
+    }
+
+    // Runs every command against `solver`, which starts out empty unless a board was given beforehand,
+    // `print` writes the board to `out`
+    // Boards loaded by the script are solved by the solvers `new_solver` makes, so they're set up
+    // like the one the script started with
+    pub fn run<W: Write>(&self, solver: &mut Option<Solver>, new_solver: impl Fn(Sudoku) -> Solver, out: &mut W) -> Result<(), String> {
+        for (line, command) in &self.commands {
+            run_command(command, solver, &new_solver, out).map_err(|message| format!("line {}: {}", line, message))?;
+        }
+        Ok(())
+    }
+}
+
+fn run_command<W: Write>(command: &Command, solver: &mut Option<Solver>, new_solver: &impl Fn(Sudoku) -> Solver, out: &mut W) -> Result<(), String> {
+    match command {
+        Command::Load(path) => *solver = Some(new_solver(formats::load(path)?)),
+        Command::Step(count) => {
+            let solver = loaded(solver)?;
+            for _ in 0..*count {
+                match solver.step() {
+                    Step::Complete => break,
+                    Step::Unsolvable => return Err("board has no solution".to_string()),
+                    Step::Collapsed { .. } | Step::Backtracked => {}
+                }
+            }
+        }
+        Command::Solve => {
+            if !loaded(solver)?.solve() {
+                return Err("board has no solution".to_string());
+            }
+        }
+        Command::Reset => loaded(solver)?.reset(),
+        Command::Collapse { x, y } => {
+            if loaded(solver)?.collapse(*x, *y)? == Step::Unsolvable {
+                return Err("board has no solution".to_string());
+            }
+        }
+        Command::Save(path) => formats::save(loaded(solver)?.board(), path)?,
+        Command::Print => write!(out, "{}", loaded(solver)?.board()).map_err(|e| e.to_string())?,
+    }
+    Ok(())
+}
+
+fn loaded(solver: &mut Option<Solver>) -> Result<&mut Solver, String> {
+    solver.as_mut().ok_or_else(|| "no board loaded".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn loads_steps_to_completion_and_saves() {
+        let dir = std::env::temp_dir().join(format!("sudoku-script-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let (puzzle, solved) = (dir.join("puzzle.txt"), dir.join("solved.txt"));
+        fs::write(&puzzle, Sudoku::sample("sudoku1").unwrap().to_string()).unwrap();
+
+        let text = format!("load {}\nstep 1000\nprint\nsave {}\n", puzzle.display(), solved.display());
+        let new_solver = |board| {
+            let mut solver = Solver::with_seed(board, 3);
+            solver.set_propagate(true);
+            solver
+        };
+        let (mut solver, mut out) = (None, vec![]);
+        Script::parse(&text).unwrap().run(&mut solver, new_solver, &mut out).unwrap();
+
+        let solver = solver.unwrap();
+        assert!(solver.propagates() && solver.seed() == 3);
+        assert!(solver.board().complete());
+        assert_eq!(String::from_utf8(out).unwrap(), solver.board().to_string());
+        assert_eq!(formats::load(solved.to_str().unwrap()).unwrap().to_string(), solver.board().to_string());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,14 +1,44 @@
+use rand::distributions::{Distribution, WeightedIndex};
 use rand::Rng;
 use rand::seq::SliceRandom;
+use std::collections::hash_map::DefaultHasher;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::RangeInclusive;
+use std::str::FromStr;
 use std::io;
-use std::fs::read_to_string;
+use std::fs::{read_to_string, write};
 
-#[derive(Debug, Clone)]
+use crate::candidates::{self, Bitmask};
+use crate::error::{CompletionError, SudokuError};
+use crate::json::Json;
+use crate::sandwich::{self, SandwichClues};
+use crate::solver::Solver;
+use crate::thermo::{self, Thermometers};
+
+#[derive(Debug)]
 pub enum CellValue {
     Certain(u32),
     Uncertain(Vec<u32>),
 }
 
+impl Clone for CellValue {
+    fn clone(&self) -> Self {
+        match self {
+            CellValue::Certain(num) => CellValue::Certain(*num),
+            CellValue::Uncertain(nums) => CellValue::Uncertain(nums.clone()),
+        }
+    }
+
+    // Keeps the list of candidates already there, which is what makes reusing a pooled board cheap
+    fn clone_from(&mut self, source: &Self) {
+        match (self, source) {
+            (CellValue::Uncertain(nums), CellValue::Uncertain(source_nums)) => nums.clone_from(source_nums),
+            (value, _) => *value = source.clone(),
+        }
+    }
+}
+
 impl CellValue {
     pub fn is_certain(&self) -> bool {
         matches!(*self, CellValue::Certain(_))
@@ -22,236 +52,3027 @@ impl CellValue {
     }
 }
 
-#[derive(Debug, Clone)]
+// Who gave a non-static cell its certain value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Placement {
+    Solver,
+    Player,
+    Assist,
+    // Filled in from the solution when the player gave up
+    Revealed,
+}
+
+#[derive(Debug)]
 pub struct Cell {
     value: CellValue,
     is_static: bool,
+    placement: Placement,
+    // Whether the player picked its candidates, recomputing them then only takes numbers away
+    candidates_locked: bool,
+}
+
+impl Clone for Cell {
+    fn clone(&self) -> Self {
+        Self {
+            value: self.value.clone(),
+            is_static: self.is_static,
+            placement: self.placement,
+            candidates_locked: self.candidates_locked,
+        }
+    }
+
+    fn clone_from(&mut self, source: &Self) {
+        self.value.clone_from(&source.value);
+        self.is_static = source.is_static;
+        self.placement = source.placement;
+        self.candidates_locked = source.candidates_locked;
+    }
 }
 
 impl Cell {
     pub fn value(&self) -> &CellValue { &self.value }
     pub fn is_static(&self) -> bool { self.is_static }
+    pub fn placement(&self) -> Placement { self.placement }
+    pub fn candidates_locked(&self) -> bool { self.candidates_locked }
 
     pub fn new(value: CellValue, is_static: bool) -> Self {
-        Self { value, is_static }
+        Self { value, is_static, placement: Placement::Solver, candidates_locked: false }
     }
 }
 
+// What `Sudoku::place` changed, so `Sudoku::retract` can undo exactly that
 #[derive(Debug, Clone)]
+pub struct PlaceToken {
+    x: usize,
+    y: usize,
+    num: u32,
+    previous: Cell,
+    // Peers that lost the number as a candidate, along with where it was in their list
+    eliminated: Vec<(usize, usize, usize)>,
+}
+
+// How much each deduction adds to `difficulty_score`, per number it places or candidate it eliminates
+const SINGLE_COST: u32 = 1;
+const HIDDEN_SINGLE_COST: u32 = 2;
+const NAKED_PAIR_COST: u32 = 5;
+const POINTING_PAIR_COST: u32 = 5;
+const NAKED_TRIPLE_COST: u32 = 8;
+const SANDWICH_COST: u32 = 5;
+const THERMO_COST: u32 = 5;
+// Per cell that has to be guessed when no deduction applies
+const GUESS_COST: u32 = 50;
+
+// A group of cells that has to hold every number once
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unit {
+    Row(usize),
+    Column(usize),
+    // Quadrant counted in quadrants, like `box_of` returns it
+    Box(usize, usize),
+    // One of the extra regions of a Windoku, as `extra_region_coords` lists them
+    Region(usize),
+}
+
+impl fmt::Display for Unit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Unit::Row(y) => write!(f, "row {}", y),
+            Unit::Column(x) => write!(f, "column {}", x),
+            Unit::Box(quadrant_x, quadrant_y) => write!(f, "quadrant ({}, {})", quadrant_x, quadrant_y),
+            Unit::Region(i) => write!(f, "Windoku region {}", i),
+        }
+    }
+}
+
+// `update_possible_values` a cell at a time, for watching the candidates narrow down across the board
+#[derive(Debug, Clone, Default)]
+pub struct CandidateSweep {
+    // Index of the next cell in reading order
+    next: usize,
+}
+
+impl CandidateSweep {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Works out the candidates of the next empty cell and returns it, or `None` once every cell is done,
+    // the sandwich clues and thermometers are only applied then since they look at whole lines
+    pub fn advance(&mut self, board: &mut Sudoku) -> Option<(usize, usize)> {
+        let cells = board.dim * board.dim;
+        while self.next < cells {
+            let (x, y) = (self.next % board.dim, self.next / board.dim);
+            self.next += 1;
+            if !board.board[board.index(x, y)].value.is_certain() {
+                board.update_possible_cell_values(x, y);
+                return Some((x, y));
+            }
+        }
+
+        if self.next == cells {
+            self.next += 1;
+            board.apply_sandwich_clues();
+            board.apply_thermometers();
+        }
+        None
+    }
+}
+
+// `difficulty_score` sorted into what a player would call it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+    Expert,
+}
+
+impl Difficulty {
+    // Scores below which a puzzle is easy, medium and hard, two guesses already make it medium
+    const THRESHOLDS: [u32; 3] = [100, 300, 600];
+
+    // `None` for the score of a board with no solution
+    pub fn from_score(score: u32) -> Option<Self> {
+        if score == u32::MAX {
+            return None;
+        }
+
+        let level = Self::THRESHOLDS.iter().filter(|&&threshold| score >= threshold).count();
+        Some([Difficulty::Easy, Difficulty::Medium, Difficulty::Hard, Difficulty::Expert][level])
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Medium => "medium",
+            Difficulty::Hard => "hard",
+            Difficulty::Expert => "expert",
+        }
+    }
+}
+
+// A deduction that returns how much progress it made
+type Technique = fn(&mut Sudoku) -> usize;
+
+// Deductions `solve_logically_with` can be told to use
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Deduction {
+    Singles,
+    HiddenSingles,
+    NakedPairs,
+    NakedTriples,
+    PointingPairs,
+    Sandwich,
+    Thermo,
+}
+
+impl Deduction {
+    // In the order `solve_logically` applies them
+    pub const ALL: [Deduction; 7] = [
+        Deduction::Singles,
+        Deduction::HiddenSingles,
+        Deduction::NakedPairs,
+        Deduction::NakedTriples,
+        Deduction::PointingPairs,
+        Deduction::Sandwich,
+        Deduction::Thermo,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Deduction::Singles => "singles",
+            Deduction::HiddenSingles => "hidden-singles",
+            Deduction::NakedPairs => "pairs",
+            Deduction::NakedTriples => "triples",
+            Deduction::PointingPairs => "pointing-pairs",
+            Deduction::Sandwich => "sandwich",
+            Deduction::Thermo => "thermo",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|deduction| deduction.name() == name)
+    }
+
+    fn apply(self, board: &mut Sudoku) -> usize {
+        let technique: Technique = match self {
+            Deduction::Singles => Sudoku::propagate_singles,
+            Deduction::HiddenSingles => Sudoku::apply_hidden_singles,
+            Deduction::NakedPairs => Sudoku::apply_naked_pairs,
+            Deduction::NakedTriples => Sudoku::apply_naked_triples,
+            Deduction::PointingPairs => Sudoku::apply_pointing_pairs,
+            Deduction::Sandwich => Sudoku::apply_sandwich_clues,
+            Deduction::Thermo => Sudoku::apply_thermometers,
+        };
+        technique(board)
+    }
+}
+
+// A set of deductions, all of them by default
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Deductions(u8);
+
+impl Default for Deductions {
+    fn default() -> Self {
+        Self::all()
+    }
+}
+
+impl Deductions {
+    pub fn all() -> Self {
+        Self::none().with_all(&Deduction::ALL)
+    }
+
+    pub fn none() -> Self {
+        Self(0)
+    }
+
+    pub fn contains(self, deduction: Deduction) -> bool {
+        self.0 & (1 << deduction as u8) != 0
+    }
+
+    pub fn with(self, deduction: Deduction) -> Self {
+        Self(self.0 | (1 << deduction as u8))
+    }
+
+    pub fn without(self, deduction: Deduction) -> Self {
+        Self(self.0 & !(1 << deduction as u8))
+    }
+
+    fn with_all(self, deductions: &[Deduction]) -> Self {
+        deductions.iter().fold(self, |set, deduction| set.with(*deduction))
+    }
+
+    // Names separated by commas, like `singles,pairs`
+    pub fn parse(list: &str) -> Result<Self, String> {
+        list.split(',').map(str::trim).filter(|name| !name.is_empty()).try_fold(Self::none(), |set, name| {
+            Deduction::from_name(name).map(|deduction| set.with(deduction)).ok_or_else(|| {
+                let names: Vec<&str> = Deduction::ALL.iter().map(|deduction| deduction.name()).collect();
+                format!("unknown deduction `{}`, expected some of: {}", name, names.join(", "))
+            })
+        })
+    }
+
+    pub fn to_list(self) -> String {
+        let names: Vec<&str> = Deduction::ALL.into_iter().filter(|d| self.contains(*d)).map(Deduction::name).collect();
+        names.join(",")
+    }
+}
+
+const SAMPLES: &[(&str, &str)] = &[
+    ("sudoku1", include_str!("../samples/sudoku1.txt")),
+    ("sudoku2", include_str!("../samples/sudoku2.txt")),
+    ("sudoku3", include_str!("../samples/sudoku3.txt")),
+    ("sudoku4", include_str!("../samples/sudoku4.txt")),
+    ("sudoku5", include_str!("../samples/sudoku5.txt")),
+    ("sudoku6", include_str!("../samples/sudoku6.txt")),
+    ("sudoku7", include_str!("../samples/sudoku7.txt")),
+    ("sudoku8", include_str!("../samples/sudoku8.txt")),
+];
+
+#[derive(Debug)]
 pub struct Sudoku {
-    // Will always be BOARD_DIM x BOARD_DIM
-    board: Vec<Vec<Cell>>,
+    // Will always be dim x dim cells, row after row, see `index`
+    board: Vec<Cell>,
+    dim: usize,
+    // Quadrants are box_rows tall and box_cols wide
+    box_rows: usize,
+    box_cols: usize,
+    sandwich: Option<SandwichClues>,
+    thermometers: Option<Thermometers>,
+    // Whether the four extra regions of a Windoku also need every number once
+    windoku: bool,
+}
+
+impl Clone for Sudoku {
+    fn clone(&self) -> Self {
+        Self {
+            board: self.board.clone(),
+            dim: self.dim,
+            box_rows: self.box_rows,
+            box_cols: self.box_cols,
+            sandwich: self.sandwich.clone(),
+            thermometers: self.thermometers.clone(),
+            windoku: self.windoku,
+        }
+    }
+
+    // Reuses the cells and candidate lists of the board being overwritten instead of allocating new ones
+    fn clone_from(&mut self, source: &Self) {
+        self.board.clone_from(&source.board);
+        self.dim = source.dim;
+        self.box_rows = source.box_rows;
+        self.box_cols = source.box_cols;
+        self.sandwich.clone_from(&source.sandwich);
+        self.thermometers.clone_from(&source.thermometers);
+        self.windoku = source.windoku;
+    }
+}
+
+// Boards that are no longer needed, like the ones dropped when backtracking, kept around
+// so the next collapse can copy into their allocations instead of making new ones
+// Counting the allocations made while collapsing the `--bench-candidates` puzzles, 20 seeds each
+// in a release build, averaged per solve:
+//   puzzle  collapses  unpooled  pooled
+//   1       8362       46473     19921
+//   2       1156       4008      2677
+// Working out the candidates after every step still allocates far more than the copies do,
+// about 4.5 million times for the first puzzle with or without the pool
+#[derive(Debug, Clone, Default)]
+pub struct BoardPool {
+    free: Vec<Sudoku>,
+}
+
+impl BoardPool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Copy of `board`, made out of a pooled board when there is one
+    pub fn copy_of(&mut self, board: &Sudoku) -> Sudoku {
+        match self.free.pop() {
+            Some(mut copy) => {
+                copy.clone_from(board);
+                copy
+            }
+            None => board.clone(),
+        }
+    }
+
+    pub fn recycle(&mut self, board: Sudoku) {
+        self.free.push(board);
+    }
 }
 
 impl Sudoku {
     pub const BOARD_DIM: usize = 9;
     pub const QUADRANT_DIM: usize = 3;
+    // Past this the cells get too small to read and the segment displays run out of letters
+    pub const MAX_DIM: usize = 25;
+
+    fn blank(dim: usize, box_rows: usize, box_cols: usize) -> Self {
+        let board = vec![Cell::new(CellValue::Uncertain(vec![]), false); dim * dim];
+        Self { board, dim, box_rows, box_cols, sandwich: None, thermometers: None, windoku: false }
+    }
+
+    // A 9x9 board without a single number on it
+    pub fn empty() -> Self {
+        Self::empty_with_dim(Self::BOARD_DIM)
+    }
+
+    // Same for any size, with the quadrants `box_shape` picks
+    pub fn empty_with_dim(dim: usize) -> Self {
+        let (box_rows, box_cols) = Self::box_shape(dim);
+        Self::blank(dim, box_rows, box_cols)
+    }
+
+    // Where the cell is in `board`
+    fn index(&self, x: usize, y: usize) -> usize {
+        y * self.dim + x
+    }
+
+    fn cell_mut(&mut self, x: usize, y: usize) -> &mut Cell {
+        let index = self.index(x, y);
+        &mut self.board[index]
+    }
+
+    // Quadrants are as close to square as possible, wider than tall when they can't be square
+    pub fn box_shape(dim: usize) -> (usize, usize) {
+        let box_rows = (1..=dim).take_while(|n| n * n <= dim).filter(|n| dim.is_multiple_of(*n)).last().unwrap_or(1);
+        (box_rows, dim / box_rows)
+    }
+
+    pub fn dim(&self) -> usize {
+        self.dim
+    }
+
+    // Numbers that go in the cells, every unit holds each of them exactly once
+    pub fn digits(&self) -> RangeInclusive<u32> {
+        1..=self.dim as u32
+    }
+
+    // Sum of the numbers in any unit of a solved board
+    pub fn magic_sum(&self) -> u32 {
+        self.digits().sum()
+    }
+
+    pub fn box_rows(&self) -> usize {
+        self.box_rows
+    }
+
+    pub fn box_cols(&self) -> usize {
+        self.box_cols
+    }
+
+    // Quadrant containing the cell, as used by `quadrant_coords`
+    pub fn box_of(&self, x: usize, y: usize) -> (usize, usize) {
+        (x / self.box_cols, y / self.box_rows)
+    }
 
     pub fn get_cell(&self, x: usize, y: usize) -> &Cell {
-        &self.board[y][x]
+        &self.board[self.index(x, y)]
     }
 
-    pub fn from_file(filepath: &str) -> io::Result<Self> {
-        let board_text = read_to_string(filepath)?;
-        let mut board = vec![vec![Cell::new(CellValue::Uncertain(vec![]), false); Self::BOARD_DIM]; Self::BOARD_DIM];
-
-        for (y, line) in board_text.lines().enumerate() {
-            for (x, char) in line.chars().enumerate() {
-                match char.to_string().parse::<u32>() {
-                    Ok(num) => {
-                        board[y][x].value = CellValue::Certain(num);
-                        board[y][x].is_static = true;
-                    }
-                    Err(_) => {}
-                }
-            }
+    // Every row from the top, each one with its cells from the left
+    pub fn rows(&self) -> impl Iterator<Item = &[Cell]> + '_ {
+        self.board.chunks(self.dim)
+    }
+
+    // The number in the cell, `None` while it is still uncertain
+    pub fn certain_value(&self, x: usize, y: usize) -> Option<u32> {
+        match self.board[self.index(x, y)].value {
+            CellValue::Certain(num) => Some(num),
+            CellValue::Uncertain(_) => None,
         }
+    }
 
-        Ok(Self { board })
+    pub fn sandwich_clues(&self) -> Option<&SandwichClues> {
+        self.sandwich.as_ref()
     }
 
-    pub fn quadrant_coords(quadrant_x: usize, quadrant_y: usize) -> Vec<(usize, usize)> {
-        assert!(quadrant_x < Self::QUADRANT_DIM && quadrant_y < Self::QUADRANT_DIM);
+    // Sandwich clues are only checked by the deductions and `complete`, the backtracking behind
+    // `count_solutions` and `solution` still sees a plain sudoku
+    pub fn set_sandwich_clues(&mut self, clues: SandwichClues) -> Result<(), String> {
+        if self.dim > SandwichClues::MAX_DIM {
+            return Err(format!("sandwich clues only work on boards up to {0}x{0}", SandwichClues::MAX_DIM));
+        }
+        if clues.rows.len() != self.dim || clues.columns.len() != self.dim {
+            return Err(format!("expected {} sandwich clues for the rows and as many for the columns", self.dim));
+        }
 
-        let quadrant_x = quadrant_x * Self::QUADRANT_DIM;
-        let quadrant_y = quadrant_y * Self::QUADRANT_DIM;
-        let mut quadrant = vec![];
+        self.sandwich = Some(clues);
+        Ok(())
+    }
 
-        for y in quadrant_y..quadrant_y + Self::QUADRANT_DIM {
-            for x in quadrant_x..quadrant_x + Self::QUADRANT_DIM {
-                quadrant.push((x, y));
+    pub fn thermometers(&self) -> Option<&Thermometers> {
+        self.thermometers.as_ref()
+    }
+
+    // Like sandwich clues, thermometers are only checked by the deductions and `complete`
+    pub fn set_thermometers(&mut self, thermometers: Thermometers) -> Result<(), String> {
+        for (i, path) in thermometers.paths.iter().enumerate() {
+            if path.len() > self.dim {
+                return Err(format!("thermometer {} is {} cells long, but only {} numbers can go up along it", i, path.len(), self.dim));
+            }
+            if let Some(&(x, y)) = path.iter().find(|&&(x, y)| x >= self.dim || y >= self.dim) {
+                return Err(format!("cell ({}, {}) of thermometer {} is outside of the board", x, y, i));
+            }
+            if let Some(pair) = path.windows(2).find(|pair| pair[0].0.abs_diff(pair[1].0) > 1 || pair[0].1.abs_diff(pair[1].1) > 1 || pair[0] == pair[1]) {
+                return Err(format!("cells {:?} and {:?} of thermometer {} are not next to each other", pair[0], pair[1], i));
             }
         }
 
-        quadrant
+        self.thermometers = Some(thermometers);
+        Ok(())
     }
 
-    pub fn row_coords(row_index: usize) -> Vec<(usize, usize)> {
-        assert!(row_index < Self::BOARD_DIM);
-        let mut row = vec![];
+    pub fn is_windoku(&self) -> bool {
+        self.windoku
+    }
 
-        for y in 0..Self::BOARD_DIM {
-            row.push((row_index, y));
+    // The extra regions sit one cell in from each corner, so they only fit on 9x9 boards
+    pub fn set_windoku(&mut self, windoku: bool) -> Result<(), String> {
+        if windoku && self.dim != Self::BOARD_DIM {
+            return Err(format!("Windoku only works on {0}x{0} boards", Self::BOARD_DIM));
         }
 
-        row
+        self.windoku = windoku;
+        Ok(())
     }
 
-    pub fn column_coords(column_index: usize) -> Vec<(usize, usize)> {
-        assert!(column_index < Self::BOARD_DIM);
-        let mut column = vec![];
-
-        for x in 0..Self::BOARD_DIM {
-            column.push((x, column_index));
+    // Cells of every Windoku region, none unless the board is a Windoku
+    pub fn extra_region_coords(&self) -> Vec<Vec<(usize, usize)>> {
+        if !self.windoku {
+            return vec![];
         }
 
-        column
+        [(1, 1), (5, 1), (1, 5), (5, 5)]
+            .into_iter()
+            .map(|(left, top)| (top..top + 3).flat_map(|y| (left..left + 3).map(move |x| (x, y))).collect())
+            .collect()
     }
 
-    pub fn update_possible_cell_values(&mut self, x: usize, y: usize) {
-        if self.board[y][x].is_static || self.board[y][x].value.is_certain() {
-            return;
+    pub fn is_static_cell(&self, x: usize, y: usize) -> bool {
+        self.board[self.index(x, y)].is_static
+    }
+
+    // Coordinates of every given, in reading order
+    pub fn static_cells(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        (0..self.dim)
+            .flat_map(move |y| (0..self.dim).map(move |x| (x, y)))
+            .filter(|&(x, y)| self.board[self.index(x, y)].is_static)
+    }
+
+    // Coordinates of every cell without a certain value, in reading order
+    pub fn empty_cells(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        (0..self.dim)
+            .flat_map(move |y| (0..self.dim).map(move |x| (x, y)))
+            .filter(|&(x, y)| !self.board[self.index(x, y)].value.is_certain())
+    }
+
+    pub fn from_file(filepath: &str) -> io::Result<Self> {
+        let board_text = read_to_string(filepath)?;
+        Self::detect_dimension(&board_text).map_err(|message| io::Error::new(io::ErrorKind::InvalidData, message))?;
+        Ok(Self::from_text(&board_text))
+    }
+
+    // A 9x9 board from its rows, like `["53..7....", "6..195...", ...]`, with `.` for empty cells,
+    // handy for writing a puzzle inline
+    pub fn new_from_rows(rows: &[&str]) -> Result<Self, SudokuError> {
+        if rows.len() != Self::BOARD_DIM {
+            return Err(SudokuError::InvalidFormat(format!("expected {} rows but found {}", Self::BOARD_DIM, rows.len())));
         }
 
-        let mut possible_values: Vec<u32> = (1..=9).collect();
-        let quadrant = Sudoku::quadrant_coords(x / Self::QUADRANT_DIM, y / Self::QUADRANT_DIM);
-        let row = Sudoku::row_coords(x);
-        let column = Sudoku::column_coords(y);
+        let board_text = rows.join("\n");
+        Self::detect_dimension(&board_text).map_err(SudokuError::InvalidFormat)?;
 
-        for (cx, cy) in quadrant.into_iter().chain(row).chain(column) {
-            if let CellValue::Certain(num) = self.board[cy][cx].value {
-                if let Some(index) = possible_values.iter().position(|x| *x == num) {
-                    possible_values.remove(index);
-                }
-            }
+        let valid = |c: char| c == '.' || c.to_digit(36).is_some_and(|num| (1..=Self::BOARD_DIM as u32).contains(&num));
+        if let Some(c) = board_text.chars().find(|&c| c != '\n' && !valid(c)) {
+            return Err(SudokuError::InvalidFormat(format!("unexpected character `{}`", c)));
         }
 
-        self.board[y][x].value = CellValue::Uncertain(possible_values);
+        let sudoku = Self::from_text(&board_text);
+        sudoku.validate_givens()?;
+        Ok(sudoku)
     }
 
-    pub fn update_possible_values(&mut self) {
-        for y in 0..Self::BOARD_DIM {
-            for x in 0..Self::BOARD_DIM {
-                self.update_possible_cell_values(x, y);
-            }
-        }
+    // Simple Sudoku files list the cells of each row separated by spaces, with blank lines between
+    // the rows of quadrants
+    pub fn from_ss(filepath: &str) -> Result<Self, String> {
+        let text = read_to_string(filepath).map_err(|e| e.to_string())?;
+        Self::from_ss_text(&text)
     }
 
-    pub fn collapse_cell(&mut self, x: usize, y: usize) -> Result<Vec<Sudoku>, String> {
-        match &self.board[y][x].value {
-            CellValue::Uncertain(numbers) => {
-                let mut possible_boards = vec![];
-                let nums = numbers.clone();
+    pub fn from_ss_text(text: &str) -> Result<Self, String> {
+        let mut board_text = String::new();
 
-                if nums.is_empty() {
-                    return Err("Cannot collapse cell with no numbers".to_string());
-                }
+        // The spaces are what tells the cells apart, so they are kept unlike in `grid_lines`
+        for line in Self::row_lines(text) {
+            for cell in line.split_whitespace().filter(|&cell| cell != "|") {
+                // Numbers past 9 can be written in full, they are stored as a single character
+                let c = match cell.parse::<u32>() {
+                    Ok(num) => char::from_digit(num, 36).ok_or(format!("invalid cell `{}`", cell))?,
+                    Err(_) if cell.chars().count() == 1 => cell.chars().next().unwrap_or('.'),
+                    Err(_) => return Err(format!("invalid cell `{}`", cell)),
+                };
+                board_text.push(c);
+            }
+            board_text.push('\n');
+        }
 
-                let mut rng = rand::thread_rng();
-                let rand_idx = rng.gen_range(0..nums.len());
+        Self::detect_dimension(&board_text)?;
+        Ok(Self::from_text(&board_text))
+    }
 
-                self.board[y][x].value = CellValue::Certain(nums[rand_idx]);
+    pub fn to_ss(&self) -> String {
+        let mut text = String::new();
 
-                for i in 0..nums.len() {
-                    if i == rand_idx {
-                        continue;
+        for y in 0..self.dim {
+            if y > 0 && y.is_multiple_of(self.box_rows) {
+                text.push('\n');
+            }
+
+            let cells: Vec<String> = (0..self.dim)
+                .map(|x| {
+                    let cell = match self.board[self.index(x, y)].value {
+                        CellValue::Certain(num) => char::from_digit(num, 36).unwrap_or('?'),
+                        CellValue::Uncertain(_) => '.',
+                    };
+                    // An extra space between quadrants
+                    if x > 0 && x.is_multiple_of(self.box_cols) {
+                        format!(" {}", cell)
+                    } else {
+                        cell.to_string()
                     }
+                })
+                .collect();
+            text.push_str(&cells.join(" "));
+            text.push('\n');
+        }
 
-                    let mut sudoku_clone = self.clone();
-                    sudoku_clone.board[y][x].value = CellValue::Certain(nums[i]);
-                    possible_boards.push(sudoku_clone);
-                }
+        text
+    }
 
-                possible_boards.shuffle(&mut rng);
-                Ok(possible_boards)
+    // Size of the board written in `board_text`, which has to be as wide as it is tall
+    // and split into quadrants of more than one row
+    pub fn detect_dimension(board_text: &str) -> Result<usize, String> {
+        let lines = Self::grid_lines(board_text);
+        let lines: Vec<&str> = lines.iter().map(|line| line.trim()).collect();
+        let width = match lines.first() {
+            Some(line) => line.chars().count(),
+            None => return Err("expected at least one row".to_string()),
+        };
+
+        for (y, line) in lines.iter().enumerate() {
+            let len = line.chars().count();
+            if len != width {
+                return Err(format!("row {} has {} cells but row 1 has {}", y + 1, len, width));
             }
-            CellValue::Certain(_) => Err("Trying to collapse cell with `Certain` value".to_string()),
         }
+
+        if lines.len() != width {
+            return Err(format!("expected {0} rows for a board {0} cells wide but found {1}", width, lines.len()));
+        }
+
+        let (box_rows, _) = Self::box_shape(width);
+        if box_rows == 1 || width > Self::MAX_DIM {
+            return Err(format!("{0}x{0} boards are not supported", width));
+        }
+
+        Ok(width)
     }
 
-    pub fn find_less_entropy(&self) -> (usize, usize) {
-        let mut index = (0, 0);
-        let mut less_entropy = usize::MAX;
+    // Lines of the text holding a row, skipping blank lines and lines like `---+---+---` that only
+    // separate quadrants
+    fn row_lines(board_text: &str) -> impl Iterator<Item = &str> {
+        board_text
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .filter(|line| !line.chars().all(|c| matches!(c, '-' | '+' | '|' | ' ')))
+    }
 
-        for y in 0..Self::BOARD_DIM {
-            for x in 0..Self::BOARD_DIM {
-                match &self.board[y][x].value {
-                    CellValue::Uncertain(numbers) => {
-                        if numbers.len() < less_entropy {
-                            index = (x, y);
-                            less_entropy = numbers.len();
-                        }
+    // Rows of the board in the text, dropping the `|` between quadrants and any spaces between cells
+    fn grid_lines(board_text: &str) -> Vec<String> {
+        Self::row_lines(board_text)
+            .map(|line| line.chars().filter(|&c| c != '|' && !c.is_whitespace()).collect())
+            .collect()
+    }
+
+    pub fn from_text(board_text: &str) -> Self {
+        let lines = Self::grid_lines(board_text);
+        let dim = lines.len();
+        let mut sudoku = Self::empty_with_dim(dim);
+
+        for (y, line) in lines.into_iter().enumerate() {
+            for (x, char) in line.chars().take(dim).enumerate() {
+                // Numbers past 9 are written as letters, `a` being 10
+                match char.to_digit(36) {
+                    Some(num) if sudoku.digits().contains(&num) => {
+                        *sudoku.cell_mut(x, y) = Cell::new(CellValue::Certain(num), true);
                     }
-                    CellValue::Certain(_) => {}
+                    _ => {}
                 }
             }
         }
 
-        index
+        sudoku
     }
 
-    pub fn reset_board(&mut self) {
-        for y in 0..Self::BOARD_DIM {
-            for x in 0..Self::BOARD_DIM {
-                if self.board[y][x].is_static {
-                    continue;
-                }
-                self.board[y][x].value = CellValue::Uncertain(vec![]);
+    // A puzzle with a single solution, made by taking givens away from a random complete board
+    // for as long as the solution stays unique, two opposite ones at a time when `symmetric`
+    pub fn generate<R: Rng>(dim: usize, symmetric: bool, rng: &mut R) -> Self {
+        let mut puzzle = Self::random_complete(dim, rng);
+        for cell in puzzle.board.iter_mut() {
+            cell.is_static = true;
+        }
+
+        let mut coords: Vec<(usize, usize)> = (0..dim).flat_map(|y| (0..dim).map(move |x| (x, y))).collect();
+        coords.shuffle(rng);
+
+        for (x, y) in coords {
+            // Already taken away along with the opposite cell
+            if !puzzle.board[puzzle.index(x, y)].is_static {
+                continue;
+            }
+
+            let mut attempt = puzzle.clone();
+            *attempt.cell_mut(x, y) = Cell::new(CellValue::Uncertain(vec![]), false);
+            if symmetric {
+                *attempt.cell_mut(dim - 1 - x, dim - 1 - y) = Cell::new(CellValue::Uncertain(vec![]), false);
+            }
+
+            if attempt.count_solutions(2) == 1 {
+                puzzle = attempt;
             }
         }
+
+        puzzle
     }
 
-    // TODO: check for duplicate numbers also just refactor this shit altogether
-    // It's not possible to have duplicates since the wave function collapse should avoid that
-    // but would be good practice to have
-    pub fn complete(&self) -> bool {
-        let expected_sum = 45;
+    // A random solved 9x9 board, none of its cells are givens
+    pub fn fill_random_valid<R: Rng>(rng: &mut R) -> Self {
+        Self::random_complete(Self::BOARD_DIM, rng)
+    }
 
-        // This needs to be checked ahead of time
-        for y in 0..Self::BOARD_DIM {
-            for x in 0..Self::BOARD_DIM {
-                if !self.board[y][x].value.is_certain() {
-                    return false;
+    fn random_complete<R: Rng>(dim: usize, rng: &mut R) -> Self {
+        let mut board = Self::empty_with_dim(dim);
+        let filled = board.fill_randomly(rng);
+        assert!(filled, "an empty board can always be completed");
+        board
+    }
+
+    // Backtracking that fills the most constrained cell first, trying its numbers in random order
+    fn fill_randomly<R: Rng>(&mut self, rng: &mut R) -> bool {
+        let mut best: Option<((usize, usize), Vec<u32>)> = None;
+
+        for (x, y) in self.empty_cells() {
+            let taken: Vec<u32> = self.peer_coords(x, y).into_iter().filter_map(|(px, py)| self.certain_value(px, py)).collect();
+            let nums: Vec<u32> = self.digits().filter(|num| !taken.contains(num)).collect();
+
+            if best.as_ref().is_none_or(|(_, best_nums)| nums.len() < best_nums.len()) {
+                // Nothing beats a cell with a single number left, or one where nothing fits
+                let forced = nums.len() <= 1;
+                best = Some(((x, y), nums));
+                if forced {
+                    break;
                 }
             }
         }
 
-        // Check sum of all quadrants
-        for qy in 0..Self::QUADRANT_DIM {
-            for qx in 0..Self::QUADRANT_DIM {
-                if Sudoku::quadrant_coords(qx, qy)
-                    .into_iter()
-                    .map(|(x, y)| self.board[y][x].value.as_vec()[0])
-                    .sum::<u32>()
-                    != expected_sum
-                {
-                    return false;
-                }
+        let Some(((x, y), mut nums)) = best else { return true };
+        nums.shuffle(rng);
+
+        for num in nums {
+            self.cell_mut(x, y).value = CellValue::Certain(num);
+            if self.fill_randomly(rng) {
+                return true;
             }
         }
 
-        for i in 0..Self::BOARD_DIM {
-            // Check sum of all rows
-            if Sudoku::row_coords(i)
-                .into_iter()
-                .map(|(x, y)| self.board[y][x].value.as_vec()[0])
-                .sum::<u32>()
-                != expected_sum
-            {
-                return false;
-            }
+        self.cell_mut(x, y).value = CellValue::Uncertain(vec![]);
+        false
+    }
 
-            // Check sum of all columns
-            if Sudoku::column_coords(i)
-                .into_iter()
-                .map(|(x, y)| self.board[y][x].value.as_vec()[0])
-                .sum::<u32>()
-                != expected_sum
-            {
-                return false;
+    // Whether the givens have a single solution and every one of them is needed for that,
+    // taking any of them away lets the board be solved in more than one way
+    pub fn is_minimal(&self) -> bool {
+        let mut puzzle = self.clone();
+        puzzle.reset_board();
+
+        if puzzle.count_solutions(2) != 1 {
+            return false;
+        }
+
+        self.static_cells().all(|(x, y)| {
+            let mut attempt = puzzle.clone();
+            *attempt.cell_mut(x, y) = Cell::new(CellValue::Uncertain(vec![]), false);
+            attempt.count_solutions(2) > 1
+        })
+    }
+
+    // Whether the givens look the same after turning the board upside down
+    pub fn has_rotational_symmetry(&self) -> bool {
+        (0..self.dim).all(|y| {
+            (0..self.dim).all(|x| self.board[self.index(x, y)].is_static == self.board[self.index(self.dim - 1 - x, self.dim - 1 - y)].is_static)
+        })
+    }
+
+    // One of the puzzles in `samples/`, embedded in the binary
+    pub fn sample(name: &str) -> Option<Self> {
+        SAMPLES
+            .iter()
+            .find(|(sample_name, _)| *sample_name == name)
+            .map(|(_, text)| Self::from_text(text))
+    }
+
+    pub fn sample_names() -> Vec<&'static str> {
+        SAMPLES.iter().map(|(name, _)| *name).collect()
+    }
+
+    // The whole board on a single line in reading order, with `.` for empty cells
+    pub fn to_line(&self) -> String {
+        self.to_string().lines().collect()
+    }
+
+    // Every cell in reading order with the numbers it can still hold, as they were left by
+    // `update_possible_values` or the deductions, a certain cell only holds its own number
+    pub fn candidates_to_json(&self) -> String {
+        let mut cells = vec![];
+
+        for y in 0..self.dim {
+            for x in 0..self.dim {
+                let value = &self.board[self.index(x, y)].value;
+                let candidates = value.as_vec().into_iter().map(|num| Json::Number(num as f64)).collect();
+                cells.push(Json::Object(vec![
+                    ("x".to_string(), Json::Number(x as f64)),
+                    ("y".to_string(), Json::Number(y as f64)),
+                    ("certain".to_string(), Json::Bool(value.is_certain())),
+                    ("candidates".to_string(), Json::Array(candidates)),
+                ]));
             }
         }
 
-        true
+        Json::Object(vec![
+            ("dim".to_string(), Json::Number(self.dim as f64)),
+            ("cells".to_string(), Json::Array(cells)),
+        ])
+        .to_string()
+    }
+
+    pub fn to_file(&self, filepath: &str) -> io::Result<()> {
+        write(filepath, self.to_string())
+    }
+
+    pub fn from_grid(grid: [[Option<u32>; 9]; 9]) -> Result<Self, SudokuError> {
+        let mut sudoku = Self::empty();
+
+        for (y, row) in grid.iter().enumerate() {
+            for (x, digit) in row.iter().enumerate() {
+                if let Some(digit) = *digit {
+                    if !sudoku.digits().contains(&digit) {
+                        return Err(SudokuError::InvalidDigit { x, y, digit });
+                    }
+                    *sudoku.cell_mut(x, y) = Cell::new(CellValue::Certain(digit), true);
+                }
+            }
+        }
+
+        sudoku.validate_givens()?;
+        Ok(sudoku)
+    }
+
+    // Certain values of a 9x9 board with 0 for every cell that isn't certain, whether it has
+    // candidates or not, the other way around from `from_grid`, `None` for boards of any other size
+    pub fn to_array(&self) -> Option<[[u32; 9]; 9]> {
+        if self.dim != Self::BOARD_DIM {
+            return None;
+        }
+        let mut grid = [[0; 9]; 9];
+
+        for (y, row) in grid.iter_mut().enumerate() {
+            for (x, num) in row.iter_mut().enumerate() {
+                if let CellValue::Certain(value) = self.board[self.index(x, y)].value {
+                    *num = value;
+                }
+            }
+        }
+
+        Some(grid)
+    }
+
+    // Whether every certain cell holds a valid digit that isn't repeated among its peers,
+    // the board doesn't need to be solvable
+    pub fn is_valid_partial(&self) -> bool {
+        let digits = self.digits();
+        let in_range = self.board.iter().all(|cell| match cell.value {
+            CellValue::Certain(num) => digits.contains(&num),
+            CellValue::Uncertain(_) => true,
+        });
+        in_range && self.conflict_count() == 0
+    }
+
+    // Copies every certain cell of `other` into this board, which is left untouched if both boards
+    // disagree on a cell or the result isn't a valid partial board
+    pub fn merge_progress(&mut self, other: &Sudoku) -> Result<(), SudokuError> {
+        if other.dim != self.dim {
+            return Err(SudokuError::DimensionMismatch { expected: self.dim, found: other.dim });
+        }
+
+        let mut merged = self.clone();
+        for y in 0..self.dim {
+            for x in 0..self.dim {
+                let theirs = match other.board[other.index(x, y)].value {
+                    CellValue::Certain(num) => num,
+                    CellValue::Uncertain(_) => continue,
+                };
+
+                match merged.board[merged.index(x, y)].value {
+                    CellValue::Certain(ours) if ours != theirs => {
+                        return Err(SudokuError::ConflictingCell { x, y, ours, theirs });
+                    }
+                    CellValue::Certain(_) => {}
+                    CellValue::Uncertain(_) => {
+                        merged.cell_mut(x, y).value = CellValue::Certain(theirs);
+                        merged.cell_mut(x, y).placement = other.board[other.index(x, y)].placement;
+                    }
+                }
+            }
+        }
+
+        if !merged.is_valid_partial() {
+            return Err(SudokuError::InconsistentBoard);
+        }
+
+        *self = merged;
+        Ok(())
+    }
+
+    // Checks that no given is repeated in its row, column or quadrant
+    pub fn validate_givens(&self) -> Result<(), SudokuError> {
+        for y in 0..self.dim {
+            for x in 0..self.dim {
+                let cell = &self.board[self.index(x, y)];
+                let digit = match cell.value {
+                    CellValue::Certain(digit) if cell.is_static => digit,
+                    _ => continue,
+                };
+
+                let repeated = self.peer_coords(x, y).into_iter().any(|(px, py)| {
+                    let peer = &self.board[self.index(px, py)];
+                    peer.is_static && matches!(peer.value, CellValue::Certain(n) if n == digit)
+                });
+
+                if repeated {
+                    return Err(SudokuError::DuplicateGiven { x, y, digit });
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn quadrant_coords(&self, quadrant_x: usize, quadrant_y: usize) -> Vec<(usize, usize)> {
+        assert!(quadrant_x < self.dim / self.box_cols && quadrant_y < self.dim / self.box_rows);
+
+        let quadrant_x = quadrant_x * self.box_cols;
+        let quadrant_y = quadrant_y * self.box_rows;
+        let mut quadrant = vec![];
+
+        for y in quadrant_y..quadrant_y + self.box_rows {
+            for x in quadrant_x..quadrant_x + self.box_cols {
+                quadrant.push((x, y));
+            }
+        }
+
+        quadrant
+    }
+
+    pub fn row_coords(&self, row_index: usize) -> Vec<(usize, usize)> {
+        assert!(row_index < self.dim);
+        let mut row = vec![];
+
+        for x in 0..self.dim {
+            row.push((x, row_index));
+        }
+
+        row
+    }
+
+    pub fn column_coords(&self, column_index: usize) -> Vec<(usize, usize)> {
+        assert!(column_index < self.dim);
+        let mut column = vec![];
+
+        for y in 0..self.dim {
+            column.push((column_index, y));
+        }
+
+        column
+    }
+
+    // Numbers that aren't certain in any cell of the row yet, in ascending order
+    pub fn missing_in_row(&self, row_index: usize) -> Vec<u32> {
+        self.missing_in(&self.row_coords(row_index))
+    }
+
+    pub fn missing_in_column(&self, column_index: usize) -> Vec<u32> {
+        self.missing_in(&self.column_coords(column_index))
+    }
+
+    pub fn missing_in_box(&self, quadrant_x: usize, quadrant_y: usize) -> Vec<u32> {
+        self.missing_in(&self.quadrant_coords(quadrant_x, quadrant_y))
+    }
+
+    fn missing_in(&self, unit: &[(usize, usize)]) -> Vec<u32> {
+        self.digits()
+            .filter(|&num| !unit.iter().any(|&(x, y)| matches!(self.board[self.index(x, y)].value, CellValue::Certain(n) if n == num)))
+            .collect()
+    }
+
+    // The row, column and quadrant of the cell
+    pub fn units_of(&self, x: usize, y: usize) -> [Unit; 3] {
+        let (quadrant_x, quadrant_y) = self.box_of(x, y);
+        [Unit::Row(y), Unit::Column(x), Unit::Box(quadrant_x, quadrant_y)]
+    }
+
+    pub fn cells_of(&self, unit: Unit) -> Vec<(usize, usize)> {
+        match unit {
+            Unit::Row(y) => self.row_coords(y),
+            Unit::Column(x) => self.column_coords(x),
+            Unit::Box(quadrant_x, quadrant_y) => self.quadrant_coords(quadrant_x, quadrant_y),
+            // Boards that aren't a Windoku have no regions, so none of their cells
+            Unit::Region(i) => self.extra_region_coords().into_iter().nth(i).unwrap_or_default(),
+        }
+    }
+
+    // Every row and column, then every quadrant and then the Windoku regions if there are any
+    pub fn all_units(&self) -> impl Iterator<Item = Unit> + '_ {
+        let lines = (0..self.dim).flat_map(|i| [Unit::Row(i), Unit::Column(i)]);
+        let boxes = (0..self.dim / self.box_rows)
+            .flat_map(move |qy| (0..self.dim / self.box_cols).map(move |qx| Unit::Box(qx, qy)));
+        let regions = (0..self.extra_region_coords().len()).map(Unit::Region);
+        lines.chain(boxes).chain(regions)
+    }
+
+    // Cells of every unit, collected so the deductions can change the board while going through them
+    fn all_unit_coords(&self) -> Vec<Vec<(usize, usize)>> {
+        self.all_units().map(|unit| self.cells_of(unit)).collect()
+    }
+
+    pub(crate) fn peer_coords(&self, x: usize, y: usize) -> Vec<(usize, usize)> {
+        let regions = self.extra_region_coords().into_iter().filter(|region| region.contains(&(x, y))).flatten();
+
+        let mut peers: Vec<(usize, usize)> = self
+            .units_of(x, y)
+            .into_iter()
+            .flat_map(|unit| self.cells_of(unit))
+            .chain(regions)
+            .filter(|&coord| coord != (x, y))
+            .collect();
+
+        // Cells sharing more than one unit with the cell would show up twice
+        peers.sort_unstable();
+        peers.dedup();
+        peers
+    }
+
+    fn candidates(&self, x: usize, y: usize) -> Option<&Vec<u32>> {
+        match &self.board[self.index(x, y)].value {
+            CellValue::Uncertain(numbers) => Some(numbers),
+            CellValue::Certain(_) => None,
+        }
+    }
+
+    fn eliminate_candidate(&mut self, x: usize, y: usize, num: u32) -> bool {
+        if let CellValue::Uncertain(numbers) = &mut self.cell_mut(x, y).value {
+            if let Some(index) = numbers.iter().position(|n| *n == num) {
+                numbers.remove(index);
+                return true;
+            }
+        }
+        false
+    }
+
+    // Makes the cell certain and removes the number from the candidates of its peers
+    fn place_value(&mut self, x: usize, y: usize, num: u32) {
+        self.cell_mut(x, y).value = CellValue::Certain(num);
+        self.cell_mut(x, y).placement = Placement::Solver;
+        for (px, py) in self.peer_coords(x, y) {
+            self.eliminate_candidate(px, py, num);
+        }
+    }
+
+    // Makes the cell certain and removes the number from the candidates of its peers without
+    // cloning the board, static cells can't be placed on
+    pub fn place(&mut self, x: usize, y: usize, num: u32) -> Option<PlaceToken> {
+        if self.board[self.index(x, y)].is_static {
+            return None;
+        }
+
+        let previous = self.board[self.index(x, y)].clone();
+        let mut eliminated = vec![];
+
+        for (px, py) in self.peer_coords(x, y) {
+            if let CellValue::Uncertain(numbers) = &mut self.cell_mut(px, py).value {
+                if let Some(index) = numbers.iter().position(|n| *n == num) {
+                    numbers.remove(index);
+                    eliminated.push((px, py, index));
+                }
+            }
+        }
+
+        self.cell_mut(x, y).value = CellValue::Certain(num);
+        self.cell_mut(x, y).placement = Placement::Solver;
+        Some(PlaceToken { x, y, num, previous, eliminated })
+    }
+
+    // Undoes a `place`, tokens have to be retracted in the opposite order they were placed in
+    pub fn retract(&mut self, token: PlaceToken) {
+        for &(px, py, index) in token.eliminated.iter().rev() {
+            if let CellValue::Uncertain(numbers) = &mut self.cell_mut(px, py).value {
+                numbers.insert(index, token.num);
+            }
+        }
+        *self.cell_mut(token.x, token.y) = token.previous;
+    }
+
+    pub fn update_possible_cell_values(&mut self, x: usize, y: usize) {
+        if self.board[self.index(x, y)].is_static || self.board[self.index(x, y)].value.is_certain() {
+            return;
+        }
+
+        let mut possible_values: Vec<u32> = self.digits().collect();
+
+        for (cx, cy) in self.peer_coords(x, y) {
+            if let CellValue::Certain(num) = self.board[self.index(cx, cy)].value {
+                if let Some(index) = possible_values.iter().position(|x| *x == num) {
+                    possible_values.remove(index);
+                }
+            }
+        }
+
+        // Numbers the player took out stay out, and the ones they left in go once a peer rules them out
+        if let (true, CellValue::Uncertain(nums)) = (self.board[self.index(x, y)].candidates_locked, &self.board[self.index(x, y)].value) {
+            possible_values.retain(|num| nums.contains(num));
+        }
+
+        self.cell_mut(x, y).value = CellValue::Uncertain(possible_values);
+    }
+
+    // Narrows the candidates of an empty cell down to `nums`, which recomputing them then keeps
+    // the same way as candidates toggled by hand, returns false for filled cells or numbers the board doesn't have
+    pub fn set_candidates(&mut self, x: usize, y: usize, nums: &[u32]) -> bool {
+        let cell = &self.board[self.index(x, y)];
+        if cell.is_static || cell.value.is_certain() || !nums.iter().all(|num| self.digits().contains(num)) {
+            return false;
+        }
+
+        let mut nums = nums.to_vec();
+        nums.sort();
+        nums.dedup();
+        self.cell_mut(x, y).value = CellValue::Uncertain(nums);
+        self.cell_mut(x, y).candidates_locked = true;
+        true
+    }
+
+    // Adds or takes away a candidate of an empty cell by hand, after which recomputing the candidates
+    // keeps the player's choice, returns false for cells that are already filled or numbers the board doesn't have
+    pub fn toggle_candidate(&mut self, x: usize, y: usize, num: u32) -> bool {
+        if self.board[self.index(x, y)].is_static || self.board[self.index(x, y)].value.is_certain() || !self.digits().contains(&num) {
+            return false;
+        }
+
+        // Start from what the board allows, the candidates may not have been computed yet
+        if !self.board[self.index(x, y)].candidates_locked {
+            self.update_possible_cell_values(x, y);
+        }
+
+        if let CellValue::Uncertain(nums) = &mut self.cell_mut(x, y).value {
+            match nums.iter().position(|&n| n == num) {
+                Some(index) => _ = nums.remove(index),
+                None => {
+                    nums.push(num);
+                    nums.sort();
+                }
+            }
+        }
+        self.cell_mut(x, y).candidates_locked = true;
+        true
+    }
+
+    pub fn update_possible_values(&mut self) {
+        let mut sweep = CandidateSweep::new();
+        while sweep.advance(self).is_some() {}
+    }
+
+    // Places every cell that has only one candidate left, returns how many were placed
+    pub fn propagate_singles(&mut self) -> usize {
+        let mut placed = 0;
+
+        for y in 0..self.dim {
+            for x in 0..self.dim {
+                if let Some(&[num]) = self.candidates(x, y).map(Vec::as_slice) {
+                    self.place_value(x, y, num);
+                    placed += 1;
+                }
+            }
+        }
+
+        placed
+    }
+
+    // Places numbers that can only go in one cell of a unit, returns how many were placed
+    pub fn apply_hidden_singles(&mut self) -> usize {
+        let mut placed = 0;
+
+        for unit in self.all_unit_coords() {
+            for num in self.digits() {
+                let mut positions = unit
+                    .iter()
+                    .filter(|&&(x, y)| self.candidates(x, y).is_some_and(|nums| nums.contains(&num)));
+
+                if let (Some(&(x, y)), None) = (positions.next(), positions.next()) {
+                    self.place_value(x, y, num);
+                    placed += 1;
+                }
+            }
+        }
+
+        placed
+    }
+
+    // Two cells of a unit sharing the same two candidates take those numbers away from
+    // the rest of the unit, returns how many candidates were eliminated
+    pub fn apply_naked_pairs(&mut self) -> usize {
+        let mut eliminated = 0;
+
+        for unit in self.all_unit_coords() {
+            for (i, &(ax, ay)) in unit.iter().enumerate() {
+                let pair = match self.candidates(ax, ay) {
+                    Some(nums) if nums.len() == 2 => nums.clone(),
+                    _ => continue,
+                };
+
+                let partner = unit[i + 1..].iter().find(|&&(bx, by)| {
+                    self.candidates(bx, by)
+                        .is_some_and(|nums| nums.len() == 2 && pair.iter().all(|n| nums.contains(n)))
+                });
+
+                if let Some(&(bx, by)) = partner {
+                    for &(x, y) in &unit {
+                        if (x, y) == (ax, ay) || (x, y) == (bx, by) {
+                            continue;
+                        }
+                        for &num in &pair {
+                            if self.eliminate_candidate(x, y, num) {
+                                eliminated += 1;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        eliminated
+    }
+
+    // Three cells of a unit whose candidates are all among the same three numbers take those
+    // numbers away from the rest of the unit, returns how many candidates were eliminated
+    pub fn apply_naked_triples(&mut self) -> usize {
+        let mut eliminated = 0;
+
+        for unit in self.all_unit_coords() {
+            let cells: Vec<(usize, usize)> = unit
+                .iter()
+                .copied()
+                .filter(|&(x, y)| self.candidates(x, y).is_some_and(|nums| (2..=3).contains(&nums.len())))
+                .collect();
+
+            for i in 0..cells.len() {
+                for j in i + 1..cells.len() {
+                    for k in j + 1..cells.len() {
+                        let triple = [cells[i], cells[j], cells[k]];
+                        let mut nums: Vec<u32> =
+                            triple.iter().flat_map(|&(x, y)| self.candidates(x, y).cloned().unwrap_or_default()).collect();
+                        nums.sort();
+                        nums.dedup();
+
+                        if nums.len() != 3 {
+                            continue;
+                        }
+
+                        for &(x, y) in unit.iter().filter(|coords| !triple.contains(coords)) {
+                            for &num in &nums {
+                                if self.eliminate_candidate(x, y, num) {
+                                    eliminated += 1;
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        eliminated
+    }
+
+    // Removes the candidates that can't be part of any way of making the sums of the sandwich
+    // clues, returns how many candidates were eliminated
+    pub fn apply_sandwich_clues(&mut self) -> usize {
+        let Some(clues) = self.sandwich.clone() else { return 0 };
+        let lines = (0..self.dim)
+            .map(|i| (clues.rows[i], self.row_coords(i)))
+            .chain((0..self.dim).map(|i| (clues.columns[i], self.column_coords(i))))
+            .collect::<Vec<_>>();
+        let mut eliminated = 0;
+
+        for (clue, coords) in lines {
+            let Some(clue) = clue else { continue };
+            let candidates: Vec<u64> = coords
+                .iter()
+                .map(|&(x, y)| self.board[self.index(x, y)].value.as_vec().iter().map(|num| 1u64 << num).sum())
+                .collect();
+            let allowed = sandwich::allowed_in_line(&candidates, clue, self.dim as u32);
+
+            for (&(x, y), allowed) in coords.iter().zip(allowed) {
+                for num in self.board[self.index(x, y)].value.as_vec() {
+                    if allowed & (1 << num) == 0 && self.eliminate_candidate(x, y, num) {
+                        eliminated += 1;
+                    }
+                }
+            }
+        }
+
+        eliminated
+    }
+
+    // Removes the candidates too small or too large to keep the numbers going up along every
+    // thermometer, returns how many candidates were eliminated
+    pub fn apply_thermometers(&mut self) -> usize {
+        let Some(thermometers) = self.thermometers.clone() else { return 0 };
+        let mut eliminated = 0;
+
+        for path in &thermometers.paths {
+            let candidates: Vec<Vec<u32>> = path.iter().map(|&(x, y)| self.board[self.index(x, y)].value.as_vec()).collect();
+            // Without room for every cell the whole thermometer is a dead end
+            let bounds = thermo::bounds(&candidates).unwrap_or_else(|| vec![(1, 0); path.len()]);
+
+            for (&(x, y), (low, high)) in path.iter().zip(bounds) {
+                for num in self.board[self.index(x, y)].value.as_vec() {
+                    if !(low..=high).contains(&num) && self.eliminate_candidate(x, y, num) {
+                        eliminated += 1;
+                    }
+                }
+            }
+        }
+
+        eliminated
+    }
+
+    // When a number can only go in a single row or column of a quadrant it can't go anywhere
+    // else in that row or column, returns how many candidates were eliminated
+    pub fn apply_pointing_pairs(&mut self) -> usize {
+        let mut eliminated = 0;
+
+        for qy in 0..self.dim / self.box_rows {
+            for qx in 0..self.dim / self.box_cols {
+                let quadrant = self.quadrant_coords(qx, qy);
+
+                for num in self.digits() {
+                    let positions: Vec<(usize, usize)> = quadrant
+                        .iter()
+                        .copied()
+                        .filter(|&(x, y)| self.candidates(x, y).is_some_and(|nums| nums.contains(&num)))
+                        .collect();
+
+                    if positions.len() < 2 {
+                        continue;
+                    }
+
+                    let line = if positions.iter().all(|&(_, y)| y == positions[0].1) {
+                        self.row_coords(positions[0].1)
+                    } else if positions.iter().all(|&(x, _)| x == positions[0].0) {
+                        self.column_coords(positions[0].0)
+                    } else {
+                        continue;
+                    };
+
+                    for (x, y) in line {
+                        if quadrant.contains(&(x, y)) {
+                            continue;
+                        }
+                        if self.eliminate_candidate(x, y, num) {
+                            eliminated += 1;
+                        }
+                    }
+                }
+            }
+        }
+
+        eliminated
+    }
+
+    // Applies every deduction technique until none of them makes progress,
+    // returns whether the board got solved without having to guess
+    pub fn solve_logically(&mut self) -> bool {
+        self.solve_logically_with(Deductions::all())
+    }
+
+    // Like `solve_logically` but only with the given deductions, so it can stall earlier
+    pub fn solve_logically_with(&mut self, deductions: Deductions) -> bool {
+        self.update_possible_values();
+
+        loop {
+            let progress: usize = Deduction::ALL
+                .into_iter()
+                .filter(|deduction| deductions.contains(*deduction))
+                .map(|deduction| deduction.apply(self))
+                .sum();
+
+            if progress == 0 {
+                break;
+            }
+        }
+
+        self.complete()
+    }
+
+    // Solves the board the way a person would, always going for the cheapest deduction and only guessing
+    // when none applies, adding up what each step costs, boards with no solution get `u32::MAX`
+    pub fn difficulty_score(&self) -> u32 {
+        // The backtracking behind `solution` sees a plain sudoku, so the guesses on boards with
+        // sandwich clues or thermometers come from the solver, which checks those too
+        let solution = if self.sandwich.is_some() || self.thermometers.is_some() {
+            let mut solver = Solver::with_seed(self.clone(), 0);
+            solver.solve().then(|| solver.board().clone())
+        } else {
+            self.solution()
+        };
+        let Some(solution) = solution else {
+            return u32::MAX;
+        };
+
+        let techniques: [(Technique, u32); 7] = [
+            (Sudoku::propagate_singles, SINGLE_COST),
+            (Sudoku::apply_hidden_singles, HIDDEN_SINGLE_COST),
+            (Sudoku::apply_naked_pairs, NAKED_PAIR_COST),
+            (Sudoku::apply_pointing_pairs, POINTING_PAIR_COST),
+            (Sudoku::apply_sandwich_clues, SANDWICH_COST),
+            (Sudoku::apply_thermometers, THERMO_COST),
+            (Sudoku::apply_naked_triples, NAKED_TRIPLE_COST),
+        ];
+
+        let mut board = self.clone();
+        board.update_possible_values();
+        let mut score = 0;
+
+        while !board.is_filled() {
+            let cost = techniques.iter().find_map(|(apply, cost)| {
+                let progress = apply(&mut board) as u32;
+                (progress > 0).then_some(progress * cost)
+            });
+
+            match cost {
+                Some(cost) => score += cost,
+                // Guessing right straight away, a person would likely need more than one try
+                None => {
+                    let (x, y) = board.most_constrained_empty_cell().expect("board isn't filled");
+                    if let CellValue::Certain(num) = solution.board[solution.index(x, y)].value {
+                        board.place_value(x, y, num);
+                    }
+                    score += GUESS_COST;
+                }
+            }
+        }
+
+        score
+    }
+
+    // `None` for boards with no solution
+    pub fn difficulty(&self) -> Option<Difficulty> {
+        Difficulty::from_score(self.difficulty_score())
+    }
+
+    pub fn collapse_cell(&mut self, x: usize, y: usize) -> Result<Vec<Sudoku>, String> {
+        self.collapse_cell_with(x, y, &mut rand::thread_rng())
+    }
+
+    pub fn collapse_cell_with<R: Rng>(&mut self, x: usize, y: usize, rng: &mut R) -> Result<Vec<Sudoku>, String> {
+        self.collapse_cell_pooled(x, y, rng, &mut BoardPool::new())
+    }
+
+    // Like `collapse_cell_with`, but the other boards are copied into boards taken from `pool`
+    pub fn collapse_cell_pooled<R: Rng>(
+        &mut self,
+        x: usize,
+        y: usize,
+        rng: &mut R,
+        pool: &mut BoardPool,
+    ) -> Result<Vec<Sudoku>, String> {
+        let mut possible_boards = self.collapse_cell_by(x, y, pool, |nums| rng.gen_range(0..nums.len()))?;
+        possible_boards.shuffle(rng);
+        Ok(possible_boards)
+    }
+
+    // Always collapses to the smallest candidate and leaves the other boards in ascending order,
+    // so the same board always collapses the same way
+    pub fn collapse_cell_smallest(&mut self, x: usize, y: usize) -> Result<Vec<Sudoku>, String> {
+        self.collapse_cell_smallest_pooled(x, y, &mut BoardPool::new())
+    }
+
+    pub fn collapse_cell_smallest_pooled(&mut self, x: usize, y: usize, pool: &mut BoardPool) -> Result<Vec<Sudoku>, String> {
+        self.collapse_cell_by(x, y, pool, |_| 0)
+    }
+
+    // Like `collapse_cell_with`, but candidates with a higher weight are more likely to be chosen
+    pub fn collapse_cell_weighted<R: Rng, F: Fn(u32) -> f64>(
+        &mut self,
+        x: usize,
+        y: usize,
+        rng: &mut R,
+        weight: F,
+    ) -> Result<Vec<Sudoku>, String> {
+        let mut possible_boards = self.collapse_cell_by(x, y, &mut BoardPool::new(), |nums| {
+            let weights: Vec<f64> = nums.iter().map(|num| weight(*num).max(0.0)).collect();
+
+            // Equal weights draw exactly like the uniform collapse does
+            if weights.iter().all(|w| *w == weights[0]) {
+                return rng.gen_range(0..nums.len());
+            }
+
+            match WeightedIndex::new(&weights) {
+                Ok(dist) => dist.sample(rng),
+                Err(_) => rng.gen_range(0..nums.len()),
+            }
+        })?;
+        possible_boards.shuffle(rng);
+        Ok(possible_boards)
+    }
+
+    // Collapses to the candidate at the index `choose` picks, returns a board for each of the others
+    fn collapse_cell_by<F: FnOnce(&[u32]) -> usize>(
+        &mut self,
+        x: usize,
+        y: usize,
+        pool: &mut BoardPool,
+        choose: F,
+    ) -> Result<Vec<Sudoku>, String> {
+        match &self.board[self.index(x, y)].value {
+            CellValue::Uncertain(numbers) => {
+                let mut possible_boards = vec![];
+                let nums = numbers.clone();
+
+                if nums.is_empty() {
+                    return Err("Cannot collapse cell with no numbers".to_string());
+                }
+
+                let rand_idx = choose(&nums);
+
+                self.cell_mut(x, y).value = CellValue::Certain(nums[rand_idx]);
+                self.cell_mut(x, y).placement = Placement::Solver;
+
+                for (i, num) in nums.iter().enumerate() {
+                    if i == rand_idx {
+                        continue;
+                    }
+
+                    let mut sudoku_clone = pool.copy_of(self);
+                    sudoku_clone.cell_mut(x, y).value = CellValue::Certain(*num);
+                    possible_boards.push(sudoku_clone);
+                }
+
+                Ok(possible_boards)
+            }
+            CellValue::Certain(_) => Err("Trying to collapse cell with `Certain` value".to_string()),
+        }
+    }
+
+    // Cells whose certain values differ between both boards, a cell that is certain on only one
+    // of them differs too, both boards have to be the same size
+    pub fn diff(&self, other: &Sudoku) -> Vec<(usize, usize)> {
+        assert!(self.dim == other.dim, "only boards of the same size can be compared");
+        let mut changed = vec![];
+
+        for y in 0..self.dim {
+            for x in 0..self.dim {
+                let same = match (&self.board[self.index(x, y)].value, &other.board[other.index(x, y)].value) {
+                    (CellValue::Certain(a), CellValue::Certain(b)) => a == b,
+                    (CellValue::Uncertain(_), CellValue::Uncertain(_)) => true,
+                    _ => false,
+                };
+                if !same {
+                    changed.push((x, y));
+                }
+            }
+        }
+
+        changed
+    }
+
+    // Only depends on the certain values, so boards that got to the same numbers in different ways
+    // hash the same no matter what candidates they have left
+    pub fn board_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.dim.hash(&mut hasher);
+
+        for cell in self.board.iter() {
+            match cell.value {
+                CellValue::Certain(num) => num.hash(&mut hasher),
+                CellValue::Uncertain(_) => 0u32.hash(&mut hasher),
+            }
+        }
+
+        hasher.finish()
+    }
+
+    pub fn find_less_entropy(&self) -> (usize, usize) {
+        let mut index = (0, 0);
+        let mut less_entropy = usize::MAX;
+
+        for y in 0..self.dim {
+            for x in 0..self.dim {
+                match &self.board[self.index(x, y)].value {
+                    CellValue::Uncertain(numbers) => {
+                        if numbers.len() < less_entropy {
+                            index = (x, y);
+                            less_entropy = numbers.len();
+                        }
+                    }
+                    CellValue::Certain(_) => {}
+                }
+            }
+        }
+
+        index
+    }
+
+    // The opposite of `find_less_entropy`, the uncertain cell with the most candidates left,
+    // ties go to the first one in reading order
+    pub fn max_entropy_cell(&self) -> Option<(usize, usize)> {
+        let mut best: Option<((usize, usize), usize)> = None;
+
+        for y in 0..self.dim {
+            for x in 0..self.dim {
+                if let CellValue::Uncertain(numbers) = &self.board[self.index(x, y)].value {
+                    if best.is_none_or(|(_, most_entropy)| numbers.len() > most_entropy) {
+                        best = Some(((x, y), numbers.len()));
+                    }
+                }
+            }
+        }
+
+        best.map(|(coords, _)| coords)
+    }
+
+    // Like `find_less_entropy` but counting the candidates from the certain peers, so it doesn't need
+    // `update_possible_values` beforehand, ties go to the cell with the most certain peers
+    pub fn most_constrained_empty_cell(&self) -> Option<(usize, usize)> {
+        let mut best: Option<((usize, usize), usize, usize)> = None;
+
+        for y in 0..self.dim {
+            for x in 0..self.dim {
+                if self.board[self.index(x, y)].value.is_certain() {
+                    continue;
+                }
+
+                let candidates = self.digits().filter(|&num| self.can_place(x, y, num)).count();
+                let certain_peers = self
+                    .peer_coords(x, y)
+                    .into_iter()
+                    .filter(|&(px, py)| self.board[self.index(px, py)].value.is_certain())
+                    .count();
+
+                let better = best.is_none_or(|(_, best_candidates, best_peers)| {
+                    candidates < best_candidates || (candidates == best_candidates && certain_peers > best_peers)
+                });
+                if better {
+                    best = Some(((x, y), candidates, certain_peers));
+                }
+            }
+        }
+
+        best.map(|(coords, _, _)| coords)
+    }
+
+    // How many numbers could still go in every uncertain cell added together, counted from the
+    // certain peers so it doesn't depend on when the candidates were last updated
+    pub fn total_entropy(&self) -> usize {
+        self.empty_cells()
+            .map(|(x, y)| self.digits().filter(|&num| self.can_place(x, y, num)).count())
+            .sum()
+    }
+
+    // Share of the cells that have a certain value, from 0 to 1
+    pub fn filled_fraction(&self) -> f64 {
+        let filled = self.board.iter().filter(|cell| cell.value.is_certain()).count();
+        filled as f64 / (self.dim * self.dim) as f64
+    }
+
+    // Whether putting the number in the cell keeps it from repeating in any of its units
+    pub fn can_place(&self, x: usize, y: usize, num: u32) -> bool {
+        self.peer_coords(x, y)
+            .into_iter()
+            .all(|(px, py)| !matches!(self.board[self.index(px, py)].value, CellValue::Certain(n) if n == num))
+    }
+
+    // Whether the cell is empty but none of the numbers fits in it anymore
+    pub fn is_stuck(&self, x: usize, y: usize) -> bool {
+        !self.board[self.index(x, y)].value.is_certain() && self.digits().all(|num| !self.can_place(x, y, num))
+    }
+
+    // Sets or clears (with `None`) the value of a cell, static cells can't be changed
+    pub fn set_value(&mut self, x: usize, y: usize, value: Option<u32>) -> bool {
+        if self.board[self.index(x, y)].is_static {
+            return false;
+        }
+
+        self.cell_mut(x, y).value = match value {
+            Some(num) => CellValue::Certain(num),
+            None => CellValue::Uncertain(vec![]),
+        };
+        self.cell_mut(x, y).placement = Placement::Player;
+        self.cell_mut(x, y).candidates_locked = false;
+        true
+    }
+
+    // Fills every cell that is forced by the current state of the board, either because it has
+    // a single candidate or because it's the only place left for a number in one of its units,
+    // returns the cells that got filled
+    pub fn fill_forced(&mut self) -> Vec<(usize, usize)> {
+        self.update_possible_values();
+        let mut forced = self.forced_cells();
+
+        for unit in self.all_unit_coords() {
+            for num in self.digits() {
+                let mut positions = unit
+                    .iter()
+                    .filter(|&&(x, y)| self.candidates(x, y).is_some_and(|nums| nums.contains(&num)));
+
+                if let (Some(&(x, y)), None) = (positions.next(), positions.next()) {
+                    if !forced.iter().any(|&(fx, fy, _)| (fx, fy) == (x, y)) {
+                        forced.push((x, y, num));
+                    }
+                }
+            }
+        }
+
+        let mut filled = vec![];
+        for (x, y, num) in forced {
+            // Each placement takes its number away from its peers, so a board that doesn't add up
+            // can't get the same number twice in a unit
+            if !self.candidates(x, y).is_some_and(|nums| nums.contains(&num)) {
+                continue;
+            }
+            self.place_value(x, y, num);
+            self.cell_mut(x, y).placement = Placement::Assist;
+            filled.push((x, y));
+        }
+
+        filled
+    }
+
+    // Every empty cell with a single candidate left, along with it, as the candidates stand
+    // without working them out again, what `propagate_singles` would place first
+    pub fn forced_cells(&self) -> Vec<(usize, usize, u32)> {
+        self.empty_cells()
+            .filter_map(|(x, y)| match self.candidates(x, y).map(Vec::as_slice) {
+                Some(&[num]) => Some((x, y, num)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    // Number of pairs of certain cells holding the same number in the same row, column or quadrant
+    pub fn conflict_count(&self) -> usize {
+        let mut count = 0;
+
+        for y in 0..self.dim {
+            for x in 0..self.dim {
+                let num = match self.board[self.index(x, y)].value {
+                    CellValue::Certain(num) => num,
+                    CellValue::Uncertain(_) => continue,
+                };
+
+                // Only looking forward so every pair is counted once
+                count += self
+                    .peer_coords(x, y)
+                    .into_iter()
+                    .filter(|&(px, py)| (py, px) > (y, x))
+                    .filter(|&(px, py)| matches!(self.board[self.index(px, py)].value, CellValue::Certain(n) if n == num))
+                    .count();
+            }
+        }
+
+        count
+    }
+
+    // Every cell holding `num` as a certain value
+    pub fn cells_with_value(&self, num: u32) -> Vec<(usize, usize)> {
+        let mut cells = vec![];
+
+        for y in 0..self.dim {
+            for x in 0..self.dim {
+                if let CellValue::Certain(value) = self.board[self.index(x, y)].value {
+                    if value == num {
+                        cells.push((x, y));
+                    }
+                }
+            }
+        }
+
+        cells
+    }
+
+    // How many cells hold each number as a certain value, indexed by the number itself
+    pub fn cell_count_by_value(&self) -> Vec<usize> {
+        let mut counts = vec![0; self.dim + 1];
+
+        for cell in self.board.iter() {
+            if let CellValue::Certain(num) = cell.value {
+                if let Some(count) = counts.get_mut(num as usize) {
+                    *count += 1;
+                }
+            }
+        }
+
+        counts
+    }
+
+    // How many uncertain cells still have each number as a candidate, indexed by the number itself,
+    // the numbers with the fewest places left are the ones that constrain the board the most
+    pub fn candidate_frequency(&self) -> Vec<usize> {
+        let mut counts = vec![0; self.dim + 1];
+
+        for cell in self.board.iter() {
+            if let CellValue::Uncertain(nums) = &cell.value {
+                for &num in nums {
+                    if let Some(count) = counts.get_mut(num as usize) {
+                        *count += 1;
+                    }
+                }
+            }
+        }
+
+        counts
+    }
+
+    // Copy of the board with every non-static cell set from `values`, given in reading order
+    pub(crate) fn with_values(&self, values: &[u32]) -> Self {
+        let mut sudoku = self.clone();
+
+        for (index, &num) in values.iter().enumerate() {
+            let cell = &mut sudoku.board[index];
+            if !cell.is_static {
+                cell.value = CellValue::Certain(num);
+                cell.placement = Placement::Solver;
+            }
+        }
+
+        sudoku
+    }
+
+    // Counts the solutions of the board, stopping once `limit` of them are found
+    pub fn count_solutions(&self, limit: usize) -> usize {
+        candidates::count_solutions_with::<Bitmask>(self, limit)
+    }
+
+    // Up to `limit` different solutions of the board
+    pub fn solutions(&self, limit: usize) -> Vec<Sudoku> {
+        candidates::solutions_with::<Bitmask>(self, limit)
+    }
+
+    // Whether the numbers on the board so far can still be completed into a solution
+    pub fn is_solvable(&self) -> bool {
+        self.count_solutions(1) > 0
+    }
+
+    // Any solution of the board, always the same one for the same board
+    pub fn solution(&self) -> Option<Sudoku> {
+        candidates::solve_with::<Bitmask>(self)
+    }
+
+    // What the cell holds in the solution of the board, `None` unless there is exactly one
+    pub fn solved_value(&self, x: usize, y: usize) -> Option<u32> {
+        if self.count_solutions(2) != 1 {
+            return None;
+        }
+
+        self.solution()?.certain_value(x, y)
+    }
+
+    // Fills every cell that isn't certain with its value in the solution, returns how many got filled
+    // or `None` if the board as it stands has no solution
+    pub fn reveal_solution(&mut self) -> Option<usize> {
+        let solution = self.solution()?;
+        let mut revealed = 0;
+
+        for y in 0..self.dim {
+            for x in 0..self.dim {
+                if !self.board[self.index(x, y)].value.is_certain() {
+                    self.cell_mut(x, y).value = solution.board[solution.index(x, y)].value.clone();
+                    self.cell_mut(x, y).placement = Placement::Revealed;
+                    revealed += 1;
+                }
+            }
+        }
+
+        Some(revealed)
+    }
+
+    // Filled in cells holding a different number than in `solution`, which has to be the same size,
+    // conflicts or not, givens are never counted
+    pub fn mistakes(&self, solution: &Sudoku) -> Vec<(usize, usize)> {
+        self.diff(solution)
+            .into_iter()
+            .filter(|&(x, y)| !self.board[self.index(x, y)].is_static && self.board[self.index(x, y)].value.is_certain())
+            .collect()
+    }
+
+    // Fills the empty cells among `coords` with numbers that still leave the rest of the board
+    // solvable, like solving just one quadrant, every other cell stays as it is
+    pub fn solve_region(&mut self, coords: &[(usize, usize)]) -> Result<(), SudokuError> {
+        let solution = self.solution().ok_or(SudokuError::NoSolution)?;
+
+        for &(x, y) in coords {
+            if !self.board[self.index(x, y)].value.is_certain() {
+                self.cell_mut(x, y).value = solution.board[solution.index(x, y)].value.clone();
+                self.cell_mut(x, y).placement = Placement::Solver;
+            }
+        }
+
+        Ok(())
+    }
+
+    // When the givens have no solution, finds a set of them that can't all be right: removing
+    // every one of them makes the board solvable while putting any of them back doesn't
+    pub fn explain_unsolvable(&self) -> Option<Vec<(usize, usize)>> {
+        let mut board = self.clone();
+        board.reset_board();
+
+        if board.count_solutions(1) > 0 {
+            return None;
+        }
+
+        let givens: Vec<(usize, usize)> = self.static_cells().collect();
+
+        // A single wrong given is by far the most common mistake, so try that first
+        for &(x, y) in &givens {
+            let mut candidate = board.clone();
+            *candidate.cell_mut(x, y) = Cell::new(CellValue::Uncertain(vec![]), false);
+            if candidate.count_solutions(1) > 0 {
+                return Some(vec![(x, y)]);
+            }
+        }
+
+        let mut removed = vec![];
+        for &(x, y) in &givens {
+            *board.cell_mut(x, y) = Cell::new(CellValue::Uncertain(vec![]), false);
+            removed.push((x, y));
+            if board.count_solutions(1) > 0 {
+                break;
+            }
+        }
+
+        // Put back every given that isn't needed for the board to be solvable
+        let mut i = 0;
+        while i < removed.len() {
+            let (x, y) = removed[i];
+            *board.cell_mut(x, y) = self.board[self.index(x, y)].clone();
+            if board.count_solutions(1) > 0 {
+                removed.remove(i);
+            } else {
+                *board.cell_mut(x, y) = Cell::new(CellValue::Uncertain(vec![]), false);
+                i += 1;
+            }
+        }
+
+        Some(removed)
+    }
+
+    // Whether every cell has a certain value, regardless of the board being valid
+    pub fn is_filled(&self) -> bool {
+        self.board.iter().all(|cell| cell.value.is_certain())
+    }
+
+    // Leaves the board as it was when loaded, with the givens and nothing else, the candidates
+    // are computed again by the first step like on a fresh board
+    pub fn reset_board(&mut self) {
+        for y in 0..self.dim {
+            for x in 0..self.dim {
+                if self.board[self.index(x, y)].is_static {
+                    continue;
+                }
+                *self.cell_mut(x, y) = Cell::new(CellValue::Uncertain(vec![]), false);
+            }
+        }
+    }
+
+    pub fn complete(&self) -> bool {
+        self.check_complete().is_ok()
+    }
+
+    // Like `complete` but says what's wrong, the first empty cell found, a number repeated
+    // in a unit, a line that doesn't match its sandwich clue or a thermometer that doesn't go up,
+    // checked in that order
+    pub fn check_complete(&self) -> Result<(), CompletionError> {
+        // This needs to be checked ahead of time
+        for y in 0..self.dim {
+            for x in 0..self.dim {
+                if !self.board[self.index(x, y)].value.is_certain() {
+                    return Err(CompletionError::Incomplete { x, y });
+                }
+            }
+        }
+
+        for unit in self.all_units() {
+            let mut seen = 0u64;
+            for (x, y) in self.cells_of(unit) {
+                let digit = self.certain_value(x, y).unwrap_or(0);
+                if seen & (1 << digit) != 0 {
+                    return Err(CompletionError::Duplicate { unit, digit });
+                }
+                seen |= 1 << digit;
+            }
+        }
+
+        self.check_sandwich_clues()?;
+        self.check_thermometers()
+    }
+
+    fn check_thermometers(&self) -> Result<(), CompletionError> {
+        let Some(thermometers) = &self.thermometers else { return Ok(()) };
+
+        for (thermometer, path) in thermometers.paths.iter().enumerate() {
+            for pair in path.windows(2) {
+                let (x, y) = pair[1];
+                if self.certain_value(pair[0].0, pair[0].1) >= self.certain_value(x, y) {
+                    return Err(CompletionError::ThermometerNotRising { thermometer, x, y });
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn check_sandwich_clues(&self) -> Result<(), CompletionError> {
+        let Some(clues) = &self.sandwich else { return Ok(()) };
+        let max = self.dim as u32;
+        let line = |coords: Vec<(usize, usize)>| -> Vec<u32> {
+            coords.into_iter().map(|(x, y)| self.certain_value(x, y).unwrap_or(0)).collect()
+        };
+
+        for i in 0..self.dim {
+            if let Some(clue) = clues.rows[i] {
+                if sandwich::sandwich_sum(&line(self.row_coords(i)), max) != Some(clue) {
+                    return Err(CompletionError::SandwichMismatch { line: Unit::Row(i), clue });
+                }
+            }
+            if let Some(clue) = clues.columns[i] {
+                if sandwich::sandwich_sum(&line(self.column_coords(i)), max) != Some(clue) {
+                    return Err(CompletionError::SandwichMismatch { line: Unit::Column(i), clue });
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// One row per line as `from_text` reads it, with `{:#}` the quadrants are also boxed in
+// by `|` and `+---+` lines, which `from_text` skips
+impl fmt::Display for Sudoku {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let pretty = f.alternate();
+        let border = format!("+{}", format!("{}+", "-".repeat(self.box_cols)).repeat(self.dim / self.box_cols));
+
+        for (y, row) in self.board.chunks(self.dim).enumerate() {
+            if pretty && y % self.box_rows == 0 {
+                writeln!(f, "{}", border)?;
+            }
+
+            for (x, cell) in row.iter().enumerate() {
+                if pretty && x % self.box_cols == 0 {
+                    write!(f, "|")?;
+                }
+                // Numbers past 9 are written as letters so every cell takes a single character
+                match cell.value {
+                    CellValue::Certain(num) => write!(f, "{}", std::char::from_digit(num, 36).unwrap_or('?'))?,
+                    CellValue::Uncertain(_) => write!(f, ".")?,
+                }
+            }
+
+            if pretty {
+                write!(f, "|")?;
+            }
+            writeln!(f)?;
+        }
+
+        if pretty {
+            writeln!(f, "{}", border)?;
+        }
+        Ok(())
+    }
+}
+
+// Parses a whole board written on a single line in reading order, as in `to_line`
+impl FromStr for Sudoku {
+    type Err = SudokuError;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let line = line.trim();
+        let len = line.chars().count();
+        let dim = (1..=len).find(|n| n * n >= len).unwrap_or(0);
+
+        if len == 0 || dim * dim != len {
+            return Err(SudokuError::InvalidFormat(format!(
+                "expected a square number of cells in a single line but found {}",
+                len
+            )));
+        }
+
+        if let Some(c) = line.chars().find(|c| !c.is_ascii_alphanumeric() && *c != '.') {
+            return Err(SudokuError::InvalidFormat(format!("unexpected character `{}`", c)));
+        }
+
+        let chars: Vec<char> = line.chars().collect();
+        let rows: Vec<String> = chars.chunks(dim).map(|row| row.iter().collect()).collect();
+        let text = rows.join("\n");
+        // Same sizes as the other formats, the number of cells being square isn't enough
+        Self::detect_dimension(&text).map_err(SudokuError::InvalidFormat)?;
+        Ok(Self::from_text(&text))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn blank_text(dim: usize) -> String {
+        vec![".".repeat(dim); dim].join("\n")
+    }
+
+    // Known to need guessing, no deduction gets it done
+    const HARD_PUZZLE: &str = "8........\n..36.....\n.7..9.2..\n.5...7...\n....457..\n...1...3.\n..1....68\n..85...1.\n.9....4..\n";
+
+    // An empty 9x9 board where every cell can hold anything but the cells given
+    fn with_candidates(cells: &[((usize, usize), &[u32])]) -> Sudoku {
+        let mut board = Sudoku::empty();
+        board.update_possible_values();
+        for &((x, y), nums) in cells {
+            assert!(board.set_candidates(x, y, nums));
+        }
+        board
+    }
+
+    fn candidates_of(board: &Sudoku, x: usize, y: usize) -> Vec<u32> {
+        board.get_cell(x, y).value().as_vec()
+    }
+
+    #[test]
+    fn builds_boards_from_grids() {
+        let mut grid = [[None; 9]; 9];
+        grid[0][0] = Some(5);
+        grid[4][7] = Some(9);
+        let board = Sudoku::from_grid(grid).unwrap();
+
+        assert_eq!(board.certain_value(0, 0), Some(5));
+        assert_eq!(board.certain_value(7, 4), Some(9));
+        assert!(board.get_cell(7, 4).is_static());
+        assert!(!board.get_cell(1, 0).value().is_certain());
+        assert!(!board.get_cell(1, 0).is_static());
+    }
+
+    #[test]
+    fn round_trips_grids_through_arrays() {
+        let board = Sudoku::sample("sudoku1").unwrap();
+        let array = board.to_array().unwrap();
+        assert_eq!(array[0], [5, 3, 0, 0, 7, 0, 0, 0, 0]);
+
+        let grid = array.map(|row| row.map(|num| (num != 0).then_some(num)));
+        let back = Sudoku::from_grid(grid).unwrap();
+        assert_eq!(back.to_string(), board.to_string());
+        assert_eq!(back.to_array(), Some(array));
+
+        assert_eq!(Sudoku::sample("sudoku8").unwrap().to_array(), None);
+    }
+
+    #[test]
+    fn rejects_invalid_grids() {
+        let mut grid = [[None; 9]; 9];
+        grid[2][3] = Some(10);
+        assert_eq!(Sudoku::from_grid(grid).unwrap_err(), SudokuError::InvalidDigit { x: 3, y: 2, digit: 10 });
+
+        grid[2][3] = Some(0);
+        assert_eq!(Sudoku::from_grid(grid).unwrap_err(), SudokuError::InvalidDigit { x: 3, y: 2, digit: 0 });
+
+        let mut grid = [[None; 9]; 9];
+        grid[0][0] = Some(4);
+        grid[0][8] = Some(4);
+        assert!(matches!(Sudoku::from_grid(grid), Err(SudokuError::DuplicateGiven { digit: 4, .. })));
+    }
+
+    #[test]
+    fn solves_rectangular_quadrants() {
+        let board = Sudoku::sample("sudoku8").unwrap();
+        assert_eq!((board.dim(), board.box_rows(), board.box_cols()), (6, 2, 3));
+        assert_eq!(board.box_of(4, 3), (1, 1));
+        assert_eq!(board.quadrant_coords(1, 0), vec![(3, 0), (4, 0), (5, 0), (3, 1), (4, 1), (5, 1)]);
+
+        let solution = board.solution().unwrap();
+        assert_eq!(solution.check_complete(), Ok(()));
+        for quadrant_y in 0..3 {
+            for quadrant_x in 0..2 {
+                let mut nums: Vec<u32> = solution
+                    .quadrant_coords(quadrant_x, quadrant_y)
+                    .into_iter()
+                    .map(|(x, y)| solution.certain_value(x, y).unwrap())
+                    .collect();
+                nums.sort();
+                assert_eq!(nums, vec![1, 2, 3, 4, 5, 6]);
+            }
+        }
+    }
+
+    #[test]
+    fn recognizes_filled_boards() {
+        let solved = Sudoku::sample("sudoku1").unwrap().solution().unwrap();
+        assert!(solved.is_filled());
+        assert!(solved.complete());
+
+        let mut wrong = solved.clone();
+        // Neither of them is a given
+        let (a, b) = (solved.certain_value(2, 0).unwrap(), solved.certain_value(3, 0).unwrap());
+        assert!(wrong.set_value(2, 0, Some(b)) && wrong.set_value(3, 0, Some(a)));
+        assert!(wrong.is_filled());
+        assert!(!wrong.complete());
+    }
+
+    #[test]
+    fn tells_why_boards_are_not_complete() {
+        let puzzle = Sudoku::sample("sudoku1").unwrap();
+        let solved = puzzle.solution().unwrap();
+        assert_eq!(solved.check_complete(), Ok(()));
+        assert_eq!(puzzle.check_complete(), Err(CompletionError::Incomplete { x: 2, y: 0 }));
+
+        let mut swapped = solved.clone();
+        let (a, b) = (solved.certain_value(2, 0).unwrap(), solved.certain_value(3, 0).unwrap());
+        swapped.set_value(2, 0, Some(b));
+        swapped.set_value(3, 0, Some(a));
+        assert_eq!(swapped.check_complete(), Err(CompletionError::Duplicate { unit: Unit::Column(2), digit: b }));
+
+        let mut sandwich = solved.clone();
+        let mut rows = vec![None; 9];
+        rows[4] = Some(100);
+        sandwich.set_sandwich_clues(SandwichClues { rows, columns: vec![None; 9] }).unwrap();
+        assert_eq!(sandwich.check_complete(), Err(CompletionError::SandwichMismatch { line: Unit::Row(4), clue: 100 }));
+
+        let mut thermo = solved.clone();
+        let (high, low) = if solved.certain_value(0, 8) > solved.certain_value(1, 8) { ((0, 8), (1, 8)) } else { ((1, 8), (0, 8)) };
+        thermo.set_thermometers(Thermometers { paths: vec![vec![(4, 4)], vec![high, low]] }).unwrap();
+        assert_eq!(thermo.check_complete(), Err(CompletionError::ThermometerNotRising { thermometer: 1, x: low.0, y: low.1 }));
+    }
+
+    // Every cell as whether it's certain and what it holds, candidates in their order
+    fn cell_states(board: &Sudoku) -> Vec<(bool, Vec<u32>)> {
+        (0..board.dim() * board.dim())
+            .map(|i| board.get_cell(i % board.dim(), i / board.dim()).value())
+            .map(|value| (value.is_certain(), value.as_vec()))
+            .collect()
+    }
+
+    #[test]
+    fn hashes_certain_values_only() {
+        let mut board = Sudoku::sample("sudoku1").unwrap();
+        board.update_possible_values();
+        let mut reordered = board.clone();
+        let nums = candidates_of(&reordered, 2, 0);
+        let reversed: Vec<u32> = nums.iter().rev().copied().collect();
+        reordered.cell_mut(2, 0).value = CellValue::Uncertain(reversed);
+        assert_ne!(candidates_of(&board, 2, 0), candidates_of(&reordered, 2, 0));
+        assert_eq!(board.board_hash(), reordered.board_hash());
+
+        let mut emptied = board.clone();
+        emptied.cell_mut(2, 0).value = CellValue::Uncertain(vec![]);
+        assert_eq!(board.board_hash(), emptied.board_hash());
+
+        reordered.set_value(2, 0, Some(nums[0]));
+        assert_ne!(board.board_hash(), reordered.board_hash());
+    }
+
+    #[test]
+    fn retract_undoes_place() {
+        let mut board = Sudoku::sample("sudoku1").unwrap();
+        board.update_possible_values();
+        let before = cell_states(&board);
+
+        let first = board.place(2, 0, 4).unwrap();
+        let second = board.place(2, 1, 2).unwrap();
+        assert_eq!(board.certain_value(2, 0), Some(4));
+        assert!(!candidates_of(&board, 8, 0).contains(&4));
+        assert_ne!(cell_states(&board), before);
+
+        board.retract(second);
+        board.retract(first);
+        assert_eq!(cell_states(&board), before);
+        assert!(board.place(0, 0, 1).is_none(), "givens can't be placed on");
+    }
+
+    #[test]
+    fn reset_restores_loaded_board() {
+        let loaded = Sudoku::sample("sudoku1").unwrap();
+        let mut board = loaded.clone();
+        board.update_possible_values();
+        board.set_value(2, 0, Some(4));
+        board.set_candidates(3, 0, &[2, 6]);
+        board.reveal_solution();
+        board.reset_board();
+
+        assert_eq!(board.to_string(), loaded.to_string());
+        assert_eq!(cell_states(&board), cell_states(&loaded));
+        for y in 0..board.dim() {
+            for x in 0..board.dim() {
+                let (cell, fresh) = (board.get_cell(x, y), loaded.get_cell(x, y));
+                assert_eq!(cell.is_static(), fresh.is_static());
+                assert_eq!(cell.placement(), fresh.placement());
+                assert_eq!(cell.candidates_locked(), fresh.candidates_locked());
+            }
+        }
+    }
+
+    #[test]
+    fn sweeps_to_the_same_candidates() {
+        let mut thermo = Sudoku::from_text(include_str!("../samples/thermo1.txt"));
+        thermo.set_thermometers(Thermometers::parse(include_str!("../samples/thermo1.thermo")).unwrap()).unwrap();
+        let mut sandwich = Sudoku::from_text(include_str!("../samples/sandwich1.txt"));
+        sandwich.set_sandwich_clues(SandwichClues::parse(include_str!("../samples/sandwich1.sandwich")).unwrap()).unwrap();
+        let mut locked = Sudoku::sample("sudoku1").unwrap();
+        locked.set_candidates(2, 0, &[4, 6]);
+
+        for board in [Sudoku::sample("sudoku1").unwrap(), Sudoku::sample("sudoku8").unwrap(), thermo, sandwich, locked] {
+            let mut batch = board.clone();
+            batch.update_possible_values();
+
+            let mut swept = board.clone();
+            let mut sweep = CandidateSweep::new();
+            let mut visited = 0;
+            while let Some((x, y)) = sweep.advance(&mut swept) {
+                assert!(!board.get_cell(x, y).value().is_certain());
+                visited += 1;
+            }
+            assert_eq!(visited, board.empty_cells().count());
+            assert!(sweep.advance(&mut swept).is_none());
+            assert_eq!(cell_states(&swept), cell_states(&batch));
+        }
+    }
+
+    #[test]
+    fn two_guesses_make_a_puzzle_medium() {
+        assert_eq!(Difficulty::from_score(GUESS_COST * 2 - 1), Some(Difficulty::Easy));
+        assert_eq!(Difficulty::from_score(GUESS_COST * 2), Some(Difficulty::Medium));
+        assert_eq!(Difficulty::from_score(u32::MAX), None);
+    }
+
+    #[test]
+    fn parses_single_lines() {
+        let board = Sudoku::sample("sudoku1").unwrap();
+        let parsed: Sudoku = board.to_line().parse().unwrap();
+        assert_eq!(parsed.to_string(), board.to_string());
+        assert_eq!(parsed.static_cells().collect::<Vec<_>>(), board.static_cells().collect::<Vec<_>>());
+
+        let with_letters = format!("{}\n", ".".repeat(16 * 16 - 1) + "g");
+        assert_eq!(with_letters.parse::<Sudoku>().unwrap().certain_value(15, 15), Some(16));
+
+        for line in ["", "123", "12345678?", &".".repeat(49), &".".repeat(26 * 26)] {
+            assert!(line.parse::<Sudoku>().is_err(), "`{}` was accepted", line);
+        }
+    }
+
+    #[test]
+    fn fills_exactly_the_forced_cells() {
+        let solved = Sudoku::sample("sudoku1").unwrap().solution().unwrap();
+        let mut board = solved.clone();
+        for (x, y) in [(2, 0), (4, 4), (6, 8)] {
+            board.set_value(x, y, None);
+        }
+
+        let mut filled = board.fill_forced();
+        filled.sort();
+        assert_eq!(filled, [(2, 0), (4, 4), (6, 8)]);
+        assert_eq!(board.to_string(), solved.to_string());
+        assert!(filled.iter().all(|&(x, y)| board.get_cell(x, y).placement() == Placement::Assist));
+        assert!(board.fill_forced().is_empty());
+    }
+
+    #[test]
+    fn fills_forced_cells_without_repeating_numbers() {
+        // Two cells of the first row can only be 5, and the second and third quadrants
+        // each have a single place left for 8, both of them in the second row
+        let others: Vec<u32> = (1..=9).filter(|&num| num != 8).collect();
+        let mut cells = vec![((0, 0), &[5][..]), ((1, 0), &[5][..])];
+        let quadrants = (3..9).flat_map(|x| (0..3).map(move |y| (x, y)));
+        cells.extend(quadrants.filter(|&cell| cell != (3, 1) && cell != (6, 1)).map(|cell| (cell, &others[..])));
+        let mut board = with_candidates(&cells);
+
+        board.fill_forced();
+        assert_eq!(board.conflict_count(), 0);
+        assert_eq!([board.certain_value(0, 0), board.certain_value(1, 0)].iter().flatten().count(), 1);
+        assert_eq!([board.certain_value(3, 1), board.certain_value(6, 1)].iter().flatten().count(), 1);
+    }
+
+    #[test]
+    fn tells_symmetric_givens_apart() {
+        // Givens on a diagonal turn into themselves, adding one off it breaks the symmetry
+        let diagonal: Vec<String> = (0..9).map(|y| (0..9).map(|x| if x == y { char::from_digit(x as u32 + 1, 10).unwrap() } else { '.' }).collect()).collect();
+        let mut board = Sudoku::from_text(&diagonal.join("\n"));
+        assert!(board.has_rotational_symmetry());
+
+        board.set_value(1, 0, Some(2));
+        assert!(board.has_rotational_symmetry(), "only the givens count");
+        let mut rows = diagonal.clone();
+        rows[0] = "12.......".to_string();
+        assert!(!Sudoku::from_text(&rows.join("\n")).has_rotational_symmetry());
+
+        let generated = Sudoku::generate(9, true, &mut StdRng::seed_from_u64(5));
+        assert!(generated.has_rotational_symmetry());
+        assert_eq!(generated.to_string(), Sudoku::generate(9, true, &mut StdRng::seed_from_u64(5)).to_string());
+    }
+
+    #[test]
+    fn guessing_scores_above_singles() {
+        let easy = Sudoku::sample("sudoku1").unwrap();
+        let mut singles = easy.clone();
+        assert!(singles.solve_logically_with(Deductions::parse("singles,hidden-singles").unwrap()));
+
+        let hard = Sudoku::from_text(HARD_PUZZLE);
+        assert!(hard.difficulty_score() > easy.difficulty_score() + GUESS_COST);
+        let dead_end = format!("12345678.\n........9\n{}", ".........\n".repeat(7));
+        assert_eq!(Sudoku::from_text(&dead_end).difficulty_score(), u32::MAX);
+    }
+
+    #[test]
+    fn variant_clues_count_towards_the_score() {
+        let plain = Sudoku::from_text(include_str!("../samples/thermo1.txt"));
+        let mut thermo = plain.clone();
+        thermo.set_thermometers(Thermometers::parse(include_str!("../samples/thermo1.thermo")).unwrap()).unwrap();
+        assert!(thermo.difficulty_score() < plain.difficulty_score());
+
+        let plain = Sudoku::from_text(include_str!("../samples/sandwich1.txt"));
+        let mut sandwich = plain.clone();
+        sandwich.set_sandwich_clues(SandwichClues::parse(include_str!("../samples/sandwich1.sandwich")).unwrap()).unwrap();
+        assert!(sandwich.difficulty_score() < plain.difficulty_score());
+    }
+
+    #[test]
+    fn finds_hidden_singles_in_windoku_regions() {
+        // 5 fits anywhere but the first region, where only its center is left
+        let others: Vec<u32> = (1..=9).filter(|&num| num != 5).collect();
+        let region = (1..4).flat_map(|y| (1..4).map(move |x| (x, y)));
+        let cells: Vec<_> = region.filter(|&cell| cell != (2, 2)).map(|cell| (cell, &others[..])).collect();
+
+        let mut plain = with_candidates(&cells);
+        plain.apply_hidden_singles();
+        assert_eq!(plain.certain_value(2, 2), None);
+        assert!(plain.cells_of(Unit::Region(0)).is_empty());
+
+        let mut windoku = with_candidates(&cells);
+        windoku.set_windoku(true).unwrap();
+        windoku.apply_hidden_singles();
+        assert_eq!(windoku.certain_value(2, 2), Some(5));
+        assert_eq!(windoku.cells_of(Unit::Region(0)).len(), 9);
+    }
+
+    #[test]
+    fn reads_decorated_puzzles() {
+        let decorated = "\
+            +-------+-------+-------+
+            | 5 3 . | . 7 . | . . . |
+            | 6 . . | 1 9 5 | . . . |
+            | . 9 8 | . . . | . 6 . |
+            +-------+-------+-------+
+            | 8 . . | . 6 . | . . 3 |
+            | 4 . . | 8 . 3 | . . 1 |
+            | 7 . . | . 2 . | . . 6 |
+            +-------+-------+-------+
+            | . 6 . | . . . | 2 8 . |
+            | . . . | 4 1 9 | . . 5 |
+            | . . . | . 8 . | . 7 9 |
+            +-------+-------+-------+
+        ";
+        assert_eq!(Sudoku::detect_dimension(decorated), Ok(9));
+        assert_eq!(Sudoku::from_text(decorated).to_string(), Sudoku::sample("sudoku1").unwrap().to_string());
+
+        let compact = "53.|.7.|...\n6..|195|...\n.98|...|.6.\n---+---+---\n8..|.6.|..3\n4..|8.3|..1\n7..|.2.|..6\n---+---+---\n.6.|...|28.\n...|419|..5\n...|.8.|.79\n";
+        assert_eq!(Sudoku::from_text(compact).to_string(), Sudoku::sample("sudoku1").unwrap().to_string());
+    }
+
+    #[test]
+    fn counts_cells_by_value() {
+        let board = Sudoku::sample("sudoku1").unwrap();
+        let counts = board.cell_count_by_value();
+        assert_eq!(counts, [0, 3, 2, 3, 2, 3, 5, 3, 5, 4]);
+        assert_eq!(counts.iter().sum::<usize>(), board.static_cells().count());
+        assert_eq!(Sudoku::empty().cell_count_by_value(), vec![0; 10]);
+        assert_eq!(board.solution().unwrap().cell_count_by_value()[1..], [9; 9]);
+    }
+
+    #[test]
+    fn counts_conflicts() {
+        let mut board = Sudoku::sample("sudoku1").unwrap();
+        assert_eq!(board.conflict_count(), 0);
+
+        // Same row and quadrant as the 5 in the corner, still a single pair
+        board.set_value(2, 0, Some(5));
+        assert_eq!(board.conflict_count(), 1);
+
+        let mut board = Sudoku::empty();
+        for x in [0, 4, 8] {
+            board.set_value(x, 0, Some(1));
+        }
+        board.set_value(4, 8, Some(1));
+        assert_eq!(board.conflict_count(), 4);
+        assert!(!board.is_valid_partial());
+    }
+
+    #[test]
+    fn every_sample_loads_with_consistent_givens() {
+        for name in Sudoku::sample_names() {
+            let board = Sudoku::sample(name).unwrap_or_else(|| panic!("{} didn't load", name));
+            assert!(board.validate_givens().is_ok(), "{} has inconsistent givens", name);
+        }
+        assert!(Sudoku::sample("nope").is_none());
+    }
+
+    #[test]
+    fn explains_unsolvable_puzzles() {
+        let board = Sudoku::sample("sudoku1").unwrap();
+        assert!(board.explain_unsolvable().is_none());
+
+        // The 5 in the corner turned into a 1, which doesn't repeat anything but leaves no solution
+        let text = include_str!("../samples/sudoku1.txt").replacen("53", "13", 1);
+        let board = Sudoku::from_text(&text);
+        assert!(!board.is_solvable());
+        let core = board.explain_unsolvable().unwrap();
+        assert!(core.contains(&(0, 0)), "{:?} leaves the wrong given out", core);
+    }
+
+    #[test]
+    fn derives_digits_from_the_size() {
+        let board = Sudoku::empty();
+        assert_eq!(board.digits(), 1..=9);
+        assert_eq!(board.magic_sum(), 45);
+
+        let board = Sudoku::from_text("1...\n..3.\n....\n...4\n");
+        assert_eq!(board.digits(), 1..=4);
+        assert_eq!(board.magic_sum(), 10);
+    }
+
+    #[test]
+    fn iterates_over_the_givens() {
+        let mut board = Sudoku::from_text("1...\n..3.\n....\n...4\n");
+        board.set_value(1, 0, Some(2));
+        assert_eq!(board.static_cells().collect::<Vec<_>>(), vec![(0, 0), (2, 1), (3, 3)]);
+        assert!(board.is_static_cell(2, 1));
+        assert!(!board.is_static_cell(1, 0));
+    }
+
+    #[test]
+    fn weighs_candidates_when_collapsing() {
+        let mut board = Sudoku::empty();
+        board.update_possible_values();
+
+        for seed in 0..10 {
+            let mut uniform = board.clone();
+            let uniform_boards = uniform.collapse_cell_with(0, 0, &mut StdRng::seed_from_u64(seed)).unwrap();
+            let mut weighted = board.clone();
+            let weighted_boards = weighted.collapse_cell_weighted(0, 0, &mut StdRng::seed_from_u64(seed), |_| 2.0).unwrap();
+            assert_eq!(weighted.certain_value(0, 0), uniform.certain_value(0, 0));
+            let alternatives = |boards: &[Sudoku]| boards.iter().map(|b| b.certain_value(0, 0)).collect::<Vec<_>>();
+            assert_eq!(alternatives(&weighted_boards), alternatives(&uniform_boards));
+
+            let mut skewed = board.clone();
+            let skewed_boards = skewed
+                .collapse_cell_weighted(0, 0, &mut StdRng::seed_from_u64(seed), |num| if num == 7 { 1.0 } else { 0.0 })
+                .unwrap();
+            assert_eq!(skewed.certain_value(0, 0), Some(7));
+            assert_eq!(skewed_boards.len(), 8);
+        }
+    }
+
+    #[test]
+    fn reveals_the_value_of_a_single_cell() {
+        let board = Sudoku::sample("sudoku1").unwrap();
+        assert_eq!(board.solved_value(2, 0), Some(4));
+        assert_eq!(board.solved_value(0, 0), Some(5));
+        assert_eq!(board.solved_value(2, 0), board.solution().unwrap().certain_value(2, 0));
+
+        // Far too many solutions to tell what any cell should be
+        assert_eq!(Sudoku::empty().solved_value(0, 0), None);
+    }
+
+    #[test]
+    fn merges_progress_from_another_board() {
+        let mut ours = Sudoku::sample("sudoku1").unwrap();
+        ours.set_value(2, 0, Some(4));
+        let mut theirs = Sudoku::sample("sudoku1").unwrap();
+        theirs.set_value(4, 4, Some(5));
+
+        ours.merge_progress(&theirs).unwrap();
+        assert_eq!(ours.certain_value(2, 0), Some(4));
+        assert_eq!(ours.certain_value(4, 4), Some(5));
+
+        let mut ours = Sudoku::sample("sudoku1").unwrap();
+        ours.set_value(2, 0, Some(4));
+        let mut theirs = Sudoku::sample("sudoku1").unwrap();
+        theirs.set_value(2, 0, Some(1));
+        theirs.set_value(4, 4, Some(5));
+
+        let result = ours.merge_progress(&theirs);
+        assert!(matches!(result, Err(SudokuError::ConflictingCell { x: 2, y: 0, ours: 4, theirs: 1 })));
+        assert_eq!(ours.certain_value(4, 4), None);
+    }
+
+    #[test]
+    fn reveals_the_rest_of_a_played_board() {
+        let mut board = Sudoku::sample("sudoku1").unwrap();
+        board.set_value(2, 0, Some(4));
+        board.set_value(4, 4, Some(5));
+        let empty = board.empty_cells().count();
+
+        assert_eq!(board.reveal_solution(), Some(empty));
+        assert!(board.complete());
+        assert_eq!(board.get_cell(2, 0).placement(), Placement::Player);
+        assert_eq!(board.get_cell(6, 8).placement(), Placement::Revealed);
+
+        let mut board = Sudoku::from_text(&format!("12345678.\n........9\n{}", ".........\n".repeat(7)));
+        assert_eq!(board.reveal_solution(), None);
+    }
+
+    #[test]
+    fn tells_stuck_cells_apart() {
+        let mut board = Sudoku::empty();
+        for x in 0..8 {
+            board.set_value(x, 0, Some(x as u32 + 1));
+        }
+        assert!(!board.is_stuck(8, 0));
+
+        // The only number left for the end of the row is taken by the cell under it
+        board.set_value(8, 1, Some(9));
+        assert!(board.is_stuck(8, 0));
+        assert!(!board.is_stuck(8, 2));
+        assert!(!board.is_stuck(0, 0));
+    }
+
+    #[test]
+    fn lists_missing_digits_of_units() {
+        let mut board = Sudoku::sample("sudoku1").unwrap();
+        assert_eq!(board.missing_in_row(0), [1, 2, 4, 6, 8, 9]);
+        assert_eq!(board.missing_in_column(0), [1, 2, 3, 9]);
+        assert_eq!(board.missing_in_box(0, 0), [1, 2, 4, 7]);
+
+        board.set_value(2, 0, Some(4));
+        assert_eq!(board.missing_in_row(0), [1, 2, 6, 8, 9]);
+        assert_eq!(board.missing_in_box(0, 0), [1, 2, 7]);
+        assert!(Sudoku::sample("sudoku1").unwrap().solution().unwrap().missing_in_row(4).is_empty());
+    }
+
+    #[test]
+    fn breaks_entropy_ties_by_certain_peers() {
+        let mut board = Sudoku::sample("sudoku1").unwrap().solution().unwrap();
+        assert_eq!(board.most_constrained_empty_cell(), None);
+
+        // Every emptied cell has a single number left, but the top left corner sees one empty cell
+        for (x, y) in [(0, 0), (1, 0), (8, 8)] {
+            *board.cell_mut(x, y) = Cell::new(CellValue::Uncertain(vec![]), false);
+        }
+        assert_eq!(board.most_constrained_empty_cell(), Some((8, 8)));
+    }
+
+    #[test]
+    fn exports_candidates_as_json() {
+        let mut board = Sudoku::sample("sudoku1").unwrap();
+        board.update_possible_values();
+        let json = Json::parse(&board.candidates_to_json()).unwrap();
+        assert_eq!(json.get("dim").and_then(Json::as_u64), Some(9));
+
+        let cells = json.get("cells").and_then(Json::as_array).unwrap();
+        assert_eq!(cells.len(), 81);
+        for (i, cell) in cells.iter().enumerate() {
+            let (x, y) = (i % 9, i / 9);
+            assert_eq!(cell.get("x").and_then(Json::as_u64), Some(x as u64));
+            assert_eq!(cell.get("y").and_then(Json::as_u64), Some(y as u64));
+            let candidates = cell.get("candidates").and_then(Json::as_array).unwrap();
+            let candidates: Vec<u32> = candidates.iter().map(|num| num.as_u64().unwrap() as u32).collect();
+            assert_eq!(candidates, board.get_cell(x, y).value().as_vec());
+            assert_eq!(cell.get("certain").and_then(Json::as_bool), Some(board.get_cell(x, y).value().is_certain()));
+        }
+    }
+
+    #[test]
+    fn diffs_certain_values() {
+        let board = Sudoku::sample("sudoku1").unwrap();
+        assert!(board.diff(&board.clone()).is_empty());
+
+        let mut other = board.clone();
+        other.set_value(2, 0, Some(4));
+        other.set_value(6, 8, Some(1));
+        assert_eq!(board.diff(&other), [(2, 0), (6, 8)]);
+        assert_eq!(other.diff(&board), [(2, 0), (6, 8)]);
+
+        // Only the numbers count, candidates left in the empty cells don't
+        let mut updated = other.clone();
+        updated.update_possible_values();
+        updated.set_value(6, 8, Some(3));
+        assert_eq!(other.diff(&updated), [(6, 8)]);
+    }
+
+    #[test]
+    fn pooled_copies_reuse_recycled_boards() {
+        let mut pool = BoardPool::new();
+        let fresh = pool.copy_of(&Sudoku::sample("sudoku1").unwrap());
+        let cells = fresh.board.as_ptr();
+        pool.recycle(fresh);
+
+        let board = Sudoku::sample("sudoku2").unwrap();
+        let copy = pool.copy_of(&board);
+        assert_eq!(copy.board.as_ptr(), cells);
+        assert_eq!(copy.to_string(), board.to_string());
+        assert_eq!(copy.static_cells().collect::<Vec<_>>(), board.static_cells().collect::<Vec<_>>());
+
+        // With nothing left to reuse the copy is a new board
+        assert_ne!(pool.copy_of(&board).board.as_ptr(), cells);
+    }
+
+    #[test]
+    fn counts_cells_by_candidate() {
+        let mut board = Sudoku::from_text("1...\n....\n....\n...4\n");
+        board.update_possible_values();
+        // 14 empty cells, and the 7 peers of each given can't hold its number
+        assert_eq!(board.candidate_frequency(), [0, 7, 14, 14, 7]);
+
+        let mut board = Sudoku::sample("sudoku1").unwrap();
+        board.update_possible_values();
+        let frequency = board.candidate_frequency();
+        let candidates: usize = board.empty_cells().map(|(x, y)| board.get_cell(x, y).value().as_vec().len()).sum();
+        assert_eq!(frequency.iter().sum::<usize>(), candidates);
+        assert_eq!(frequency[0], 0);
+    }
+
+    #[test]
+    fn tells_minimal_puzzles_apart() {
+        let puzzle = Sudoku::generate(9, false, &mut StdRng::seed_from_u64(1));
+        assert!(puzzle.is_minimal());
+
+        // Giving away one more number of the solution leaves it unique, but that given isn't needed
+        let (x, y) = puzzle.empty_cells().next().unwrap();
+        let num = puzzle.solved_value(x, y).unwrap();
+        let mut rows: Vec<Vec<char>> = puzzle.to_string().lines().map(|row| row.chars().collect()).collect();
+        rows[y][x] = char::from_digit(num, 10).unwrap();
+        let text: String = rows.into_iter().map(|row| row.into_iter().collect::<String>() + "\n").collect();
+        let redundant = Sudoku::from_text(&text);
+        assert_eq!(redundant.count_solutions(2), 1);
+        assert!(!redundant.is_minimal());
+
+        assert!(!Sudoku::empty().is_minimal());
+    }
+
+    #[test]
+    fn finds_the_cell_with_the_most_candidates() {
+        let mut board = Sudoku::from_text("1...\n....\n....\n...4\n");
+        board.update_possible_values();
+        // The first cell that sees neither given
+        assert_eq!(board.max_entropy_cell(), Some((2, 1)));
+        assert_eq!(board.get_cell(2, 1).value().as_vec(), [1, 2, 3, 4]);
+
+        let solved = Sudoku::sample("sudoku1").unwrap().solution().unwrap();
+        assert_eq!(solved.max_entropy_cell(), None);
+    }
+
+    #[test]
+    fn reads_certain_values() {
+        let mut board = Sudoku::sample("sudoku1").unwrap();
+        board.set_value(2, 0, Some(4));
+        assert_eq!(board.certain_value(0, 0), Some(5));
+        assert_eq!(board.certain_value(2, 0), Some(4));
+
+        // Neither a cell with candidates left nor one without any is certain
+        board.update_possible_values();
+        assert!(!board.get_cell(3, 0).value().as_vec().is_empty());
+        assert_eq!(board.certain_value(3, 0), None);
+        *board.cell_mut(5, 0) = Cell::new(CellValue::Uncertain(vec![]), false);
+        assert_eq!(board.certain_value(5, 0), None);
+        assert!(!board.complete());
+    }
+
+    #[test]
+    fn boxes_in_quadrants_when_pretty() {
+        let solved = Sudoku::sample("sudoku1").unwrap().solution().unwrap();
+        let pretty = format!("{:#}", solved);
+        let lines: Vec<&str> = pretty.lines().collect();
+        assert_eq!(lines.len(), 13);
+        for y in [0, 4, 8, 12] {
+            assert_eq!(lines[y], "+---+---+---+");
+        }
+        assert_eq!(lines[1], "|534|678|912|");
+        assert_eq!(pretty.chars().filter(|c| c.is_ascii_digit()).count(), 81);
+        assert_eq!(Sudoku::from_text(&pretty).to_string(), solved.to_string());
+
+        // 6x6 quadrants are 2 rows by 3 columns
+        let pretty = format!("{:#}", Sudoku::sample("sudoku8").unwrap());
+        assert_eq!(pretty.lines().next(), Some("+---+---+"));
+        assert_eq!(pretty.lines().filter(|line| line.starts_with('+')).count(), 4);
+    }
+
+    #[test]
+    fn copies_boards_as_a_single_line() {
+        let board = Sudoku::sample("sudoku1").unwrap();
+        assert_eq!(board.to_line(), "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79");
+
+        let solved = board.solution().unwrap();
+        assert!(solved.to_line().starts_with("534678912672195348"));
+        assert!(solved.to_line().chars().all(|c| c.is_ascii_digit()));
+        assert_eq!(Sudoku::sample("sudoku8").unwrap().to_line().len(), 36);
+    }
+
+    #[test]
+    fn stalls_without_hidden_singles() {
+        let puzzle: Sudoku = "91..235...7.8...........13...4..8....52.61.....7....284..............36....19...5".parse().unwrap();
+
+        let mut board = puzzle.clone();
+        assert!(!board.solve_logically_with(Deductions::all().without(Deduction::HiddenSingles)));
+        assert!(!board.complete());
+
+        let mut board = puzzle.clone();
+        assert!(board.solve_logically_with(Deductions::parse("singles,hidden-singles").unwrap()));
+        assert!(board.complete());
+    }
+
+    #[test]
+    fn fills_different_valid_grids() {
+        let grids: Vec<Sudoku> = (0..3).map(|seed| Sudoku::fill_random_valid(&mut StdRng::seed_from_u64(seed))).collect();
+        for grid in &grids {
+            assert!(grid.complete());
+            assert_eq!(grid.static_cells().count(), 0);
+        }
+        assert_ne!(grids[0].to_string(), grids[1].to_string());
+        assert_ne!(grids[1].to_string(), grids[2].to_string());
+
+        let again = Sudoku::fill_random_valid(&mut StdRng::seed_from_u64(0));
+        assert_eq!(again.to_string(), grids[0].to_string());
+    }
+
+    #[test]
+    fn keeps_candidates_toggled_by_hand() {
+        let mut board = Sudoku::sample("sudoku1").unwrap();
+        board.update_possible_values();
+        assert_eq!(board.get_cell(2, 0).value().as_vec(), [1, 2, 4]);
+
+        assert!(board.toggle_candidate(2, 0, 2));
+        board.update_possible_values();
+        assert_eq!(board.get_cell(2, 0).value().as_vec(), [1, 4]);
+        assert!(board.get_cell(2, 0).candidates_locked());
+
+        // A peer can still rule out a candidate that was left in
+        board.set_value(3, 0, Some(1));
+        board.update_possible_values();
+        assert_eq!(board.get_cell(2, 0).value().as_vec(), [4]);
+        assert!(!board.toggle_candidate(0, 0, 1));
+    }
+
+    #[test]
+    fn iterates_over_rows() {
+        let board = Sudoku::sample("sudoku1").unwrap();
+        let rows: Vec<&[Cell]> = board.rows().collect();
+        assert_eq!(rows.len(), Sudoku::BOARD_DIM);
+        assert!(rows.iter().all(|row| row.len() == Sudoku::BOARD_DIM));
+        assert_eq!(rows[0][4].value().as_vec(), [7]);
+        assert_eq!(rows[8][8].value().as_vec(), board.get_cell(8, 8).value().as_vec());
+
+        let board = Sudoku::sample("sudoku8").unwrap();
+        assert_eq!(board.rows().map(<[Cell]>::len).collect::<Vec<_>>(), [6; 6]);
+    }
+
+    #[test]
+    fn solves_a_single_box() {
+        let puzzle = Sudoku::sample("sudoku1").unwrap();
+        let quadrant = puzzle.quadrant_coords(1, 1);
+        let mut board = puzzle.clone();
+        board.solve_region(&quadrant).unwrap();
+
+        let filled: Vec<(usize, usize)> = quadrant.iter().copied().filter(|&(x, y)| puzzle.certain_value(x, y).is_none()).collect();
+        assert_eq!(puzzle.diff(&board), filled);
+        assert!(board.missing_in_box(1, 1).is_empty());
+        assert!(board.is_valid_partial());
+        assert!(board.is_solvable());
+
+        // Nothing gets filled on a board that already repeats a number
+        let mut conflicting = puzzle.clone();
+        conflicting.set_value(2, 0, Some(5));
+        let before = conflicting.to_string();
+        assert!(matches!(conflicting.solve_region(&quadrant), Err(SudokuError::NoSolution)));
+        assert_eq!(conflicting.to_string(), before);
+    }
+
+    #[test]
+    fn starts_out_empty() {
+        let board = Sudoku::empty();
+        assert_eq!(board.dim(), 9);
+        assert_eq!(board.static_cells().count(), 0);
+        assert_eq!(board.empty_cells().count(), 81);
+        assert!(board.rows().flatten().all(|cell| !cell.is_static() && !cell.value().is_certain()));
+    }
+
+    #[test]
+    fn lists_forced_cells() {
+        let mut board = Sudoku::sample("sudoku1").unwrap();
+        board.update_possible_values();
+        let forced = board.forced_cells();
+        let singles: Vec<_> = board
+            .empty_cells()
+            .filter_map(|(x, y)| match board.get_cell(x, y).value().as_vec().as_slice() {
+                &[num] => Some((x, y, num)),
+                _ => None,
+            })
+            .collect();
+        assert!(!forced.is_empty());
+        assert_eq!(forced, singles);
+
+        // Every forced number is the one the solution holds
+        let solution = board.solution().unwrap();
+        for (x, y, num) in forced {
+            assert_eq!(solution.certain_value(x, y), Some(num));
+        }
+    }
+
+    #[test]
+    fn builds_boards_from_rows() {
+        let rows = [
+            "53..7....",
+            "6..195...",
+            ".98....6.",
+            "8...6...3",
+            "4..8.3..1",
+            "7...2...6",
+            ".6....28.",
+            "...419..5",
+            "....8..79",
+        ];
+        let board = Sudoku::new_from_rows(&rows).unwrap();
+        assert_eq!(board.diff(&Sudoku::sample("sudoku1").unwrap()), vec![]);
+        assert_eq!(board.certain_value(0, 0), Some(5));
+        assert!(board.is_static_cell(0, 0));
+        assert_eq!(board.certain_value(2, 0), None);
+
+        assert!(matches!(Sudoku::new_from_rows(&rows[..8]), Err(SudokuError::InvalidFormat(_))));
+        assert!(matches!(Sudoku::new_from_rows(&[]), Err(SudokuError::InvalidFormat(_))));
+        let mut wrong = rows.to_vec();
+        wrong[0] = "53..x....";
+        assert!(matches!(Sudoku::new_from_rows(&wrong), Err(SudokuError::InvalidFormat(_))));
+    }
+
+    #[test]
+    fn finds_the_units_of_cells() {
+        let board = Sudoku::sample("sudoku1").unwrap();
+        assert_eq!(board.units_of(0, 0), [Unit::Row(0), Unit::Column(0), Unit::Box(0, 0)]);
+        assert_eq!(board.units_of(4, 4), [Unit::Row(4), Unit::Column(4), Unit::Box(1, 1)]);
+        assert_eq!(board.units_of(7, 2), [Unit::Row(2), Unit::Column(7), Unit::Box(2, 0)]);
+        for unit in board.units_of(5, 7) {
+            assert!(board.cells_of(unit).contains(&(5, 7)));
+        }
+
+        // The quadrants of 6x6 boards are 2 rows by 3 columns
+        let board = Sudoku::sample("sudoku8").unwrap();
+        assert_eq!(board.units_of(4, 3), [Unit::Row(3), Unit::Column(4), Unit::Box(1, 1)]);
+        assert_eq!(board.units_of(2, 5), [Unit::Row(5), Unit::Column(2), Unit::Box(0, 2)]);
+    }
+
+    #[test]
+    fn finds_mistakes_without_conflicts() {
+        let mut board = Sudoku::sample("sudoku1").unwrap();
+        let solution = board.solution().unwrap();
+        assert_eq!(board.mistakes(&solution), vec![]);
+
+        // 1 fits the row, column and quadrant of (2, 0), but the solution holds a 4 there
+        assert!(board.set_value(2, 0, Some(1)));
+        assert!(board.set_value(3, 0, solution.certain_value(3, 0)));
+        assert_eq!(board.conflict_count(), 0);
+        assert_eq!(board.mistakes(&solution), vec![(2, 0)]);
+
+        // A wrong given is the puzzle's fault, not a mistake
+        let text = include_str!("../samples/sudoku1.txt").replacen("53", "13", 1);
+        let board = Sudoku::from_text(&text);
+        assert!(board.diff(&solution).contains(&(0, 0)));
+        assert_eq!(board.mistakes(&solution), vec![]);
+    }
+
+    #[test]
+    fn solves_easy_puzzles_logically() {
+        let mut board = Sudoku::sample("sudoku1").unwrap();
+        assert!(board.solve_logically());
+        assert!(board.complete());
+    }
+
+    #[test]
+    fn does_not_guess_when_solving_logically() {
+        let mut board = Sudoku::from_text(HARD_PUZZLE);
+        assert!(!board.solve_logically());
+        assert!(!board.is_filled());
+    }
+
+    #[test]
+    fn places_naked_singles() {
+        let mut board = with_candidates(&[((0, 0), &[4])]);
+        assert_eq!(board.propagate_singles(), 1);
+        assert_eq!(board.certain_value(0, 0), Some(4));
+        assert!(!candidates_of(&board, 8, 0).contains(&4));
+    }
+
+    #[test]
+    fn places_hidden_singles() {
+        let without_five: &[u32] = &[1, 2, 3, 4, 6, 7, 8, 9];
+        let cells: Vec<_> = (0..9).filter(|&x| x != 3).map(|x| ((x, 0), without_five)).collect();
+        let mut board = with_candidates(&cells);
+        assert!(board.apply_hidden_singles() >= 1);
+        assert_eq!(board.certain_value(3, 0), Some(5));
+    }
+
+    #[test]
+    fn eliminates_naked_pairs() {
+        let mut board = with_candidates(&[((0, 0), &[1, 2]), ((1, 0), &[1, 2])]);
+        assert!(board.apply_naked_pairs() > 0);
+        assert_eq!(candidates_of(&board, 8, 0), vec![3, 4, 5, 6, 7, 8, 9]);
+        assert_eq!(candidates_of(&board, 0, 1), vec![3, 4, 5, 6, 7, 8, 9]);
+        assert_eq!(candidates_of(&board, 0, 0), vec![1, 2]);
+        assert!(candidates_of(&board, 0, 5).contains(&1));
+    }
+
+    #[test]
+    fn eliminates_naked_triples() {
+        let mut board = with_candidates(&[((0, 0), &[1, 2]), ((4, 0), &[2, 3]), ((8, 0), &[1, 3])]);
+        assert!(board.apply_naked_triples() > 0);
+        assert_eq!(candidates_of(&board, 2, 0), vec![4, 5, 6, 7, 8, 9]);
+        assert_eq!(candidates_of(&board, 4, 0), vec![2, 3]);
+        assert!(candidates_of(&board, 0, 1).contains(&3));
+    }
+
+    #[test]
+    fn eliminates_pointing_pairs() {
+        let without_seven: &[u32] = &[1, 2, 3, 4, 5, 6, 8, 9];
+        let cells: Vec<_> = (0..3).flat_map(|x| [((x, 1), without_seven), ((x, 2), without_seven)]).collect();
+        let mut board = with_candidates(&cells);
+        assert!(board.apply_pointing_pairs() > 0);
+        assert!(!candidates_of(&board, 5, 0).contains(&7));
+        assert!(candidates_of(&board, 1, 0).contains(&7));
+        assert!(candidates_of(&board, 5, 1).contains(&7));
+    }
+
+    #[test]
+    fn detects_dimension() {
+        assert_eq!(Sudoku::detect_dimension(&blank_text(4)), Ok(4));
+        assert_eq!(Sudoku::detect_dimension(&blank_text(9)), Ok(9));
+        assert_eq!(Sudoku::detect_dimension(&blank_text(25)), Ok(25));
+        assert!(Sudoku::detect_dimension(&blank_text(5)).is_err());
+        assert!(Sudoku::detect_dimension(&blank_text(26)).is_err());
+        assert!(Sudoku::detect_dimension("....\n...\n....\n....").is_err());
     }
 }
@@ -0,0 +1,201 @@
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::{Canvas, RenderTarget, TextureCreator};
+use sdl2::rwops::RWops;
+use sdl2::ttf::{Font, Sdl2TtfContext};
+
+const FONT_TFF_BYTES: &[u8] = include_bytes!("../fnt/Iosevka.ttf");
+
+// Segments lit for each character, from bit 0 to 6: top, top right, bottom right,
+// bottom, bottom left, top left and middle
+const SEGMENTS: &[(char, u8)] = &[
+    ('0', 0b0111111),
+    ('1', 0b0000110),
+    ('2', 0b1011011),
+    ('3', 0b1001111),
+    ('4', 0b1100110),
+    ('5', 0b1101101),
+    ('6', 0b1111101),
+    ('7', 0b0000111),
+    ('8', 0b1111111),
+    ('9', 0b1101111),
+    ('A', 0b1110111),
+    ('B', 0b1111100),
+    ('C', 0b0111001),
+    ('D', 0b1011110),
+    ('E', 0b1111001),
+    ('F', 0b1110001),
+    ('G', 0b0111101),
+    // The letters up to the 25 of the largest boards, as close as seven segments allow
+    ('H', 0b1110110),
+    ('I', 0b0110000),
+    ('J', 0b0011110),
+    ('K', 0b1110101),
+    ('L', 0b0111000),
+    ('M', 0b0010101),
+    ('N', 0b1010100),
+    ('O', 0b1011100),
+    ('P', 0b1110011),
+    ('-', 0b1000000),
+];
+
+pub enum Typeface<'ttf> {
+    Ttf(Font<'ttf, 'static>),
+    // Used when the font can't be loaded, only knows how to draw digits
+    Segments(u16),
+}
+
+impl<'ttf> Typeface<'ttf> {
+    pub fn load(ttf_context: Option<&'ttf Sdl2TtfContext>, size: u16) -> Self {
+        let font = ttf_context.map(|ttf_context| {
+            RWops::from_bytes(FONT_TFF_BYTES).and_then(|rwops| ttf_context.load_font_from_rwops(rwops, size))
+        });
+
+        match font {
+            Some(Ok(font)) => Typeface::Ttf(font),
+            _ => Typeface::Segments(size),
+        }
+    }
+
+    pub fn is_fallback(&self) -> bool {
+        matches!(self, Typeface::Segments(_))
+    }
+
+    pub fn size_of(&self, text: &str) -> Result<(u32, u32), String> {
+        match self {
+            Typeface::Ttf(font) => font.size_of(text).map_err(|e| e.to_string()),
+            Typeface::Segments(size) => {
+                let width = segment_advance(*size as u32) * text.chars().count() as u32;
+                Ok((width, *size as u32))
+            }
+        }
+    }
+}
+
+fn segment_advance(height: u32) -> u32 {
+    height * 3 / 4
+}
+
+fn segments_of(c: char) -> Option<u8> {
+    let c = c.to_ascii_uppercase();
+    SEGMENTS.iter().find(|(segment_char, _)| *segment_char == c).map(|(_, segments)| *segments)
+}
+
+pub fn draw_text<T: RenderTarget, C>(
+    canvas: &mut Canvas<T>,
+    texture_creator: &TextureCreator<C>,
+    typeface: &Typeface,
+    text: &str,
+    pos: (i32, i32),
+    color: Color,
+) -> Result<(), String> {
+    let font = match typeface {
+        Typeface::Ttf(font) => font,
+        Typeface::Segments(size) => return draw_text_fallback(canvas, text, pos, *size as u32, color),
+    };
+
+    let surface = font
+        .render(text)
+        .blended(color)
+        .map_err(|e| e.to_string())?;
+
+    let texture = texture_creator
+        .create_texture_from_surface(&surface)
+        .map_err(|e| e.to_string())?;
+
+    let (w, h) = surface.rect().size();
+    let target = Rect::new(pos.0 - w as i32 / 2, pos.1 - h as i32 / 2, w, h);
+
+    canvas.copy(&texture, None, Some(target))?;
+    Ok(())
+}
+
+fn draw_text_fallback<T: RenderTarget>(canvas: &mut Canvas<T>, text: &str, pos: (i32, i32), height: u32, color: Color) -> Result<(), String> {
+    let advance = segment_advance(height) as i32;
+    let start = pos.0 - advance * text.chars().count() as i32 / 2 + advance / 2;
+
+    for (i, c) in text.chars().enumerate() {
+        draw_digit_fallback(canvas, c, (start + advance * i as i32, pos.1), height, color)?;
+    }
+    Ok(())
+}
+
+// Draws the character as a seven segment display centered on `center`,
+// characters that can't be shown that way are left blank
+pub fn draw_digit_fallback<T: RenderTarget>(
+    canvas: &mut Canvas<T>,
+    c: char,
+    center: (i32, i32),
+    height: u32,
+    color: Color,
+) -> Result<(), String> {
+    let segments = match segments_of(c) {
+        Some(v) => v,
+        None => return Ok(()),
+    };
+
+    let h = height as i32;
+    let w = h / 2;
+    let t = (h / 10).max(1);
+    let (left, top) = (center.0 - w / 2, center.1 - h / 2);
+    let middle = center.1 - t / 2;
+
+    let rects = [
+        Rect::new(left, top, w as u32, t as u32),
+        Rect::new(left + w - t, top, t as u32, (h / 2) as u32),
+        Rect::new(left + w - t, center.1, t as u32, (h / 2) as u32),
+        Rect::new(left, top + h - t, w as u32, t as u32),
+        Rect::new(left, center.1, t as u32, (h / 2) as u32),
+        Rect::new(left, top, t as u32, (h / 2) as u32),
+        Rect::new(left, middle, w as u32, t as u32),
+    ];
+
+    let previous_color = canvas.draw_color();
+    canvas.set_draw_color(color);
+    for (i, rect) in rects.iter().enumerate() {
+        if segments & (1 << i) != 0 {
+            canvas.fill_rect(*rect)?;
+        }
+    }
+    canvas.set_draw_color(previous_color);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sdl2::pixels::PixelFormatEnum;
+    use sdl2::surface::Surface;
+    use std::collections::HashSet;
+
+    #[test]
+    fn digits_look_different() {
+        let glyphs: HashSet<u8> = ('1'..='9').map(|c| segments_of(c).expect("every digit has a glyph")).collect();
+        assert_eq!(glyphs.len(), 9);
+        assert!(!glyphs.contains(&0));
+        assert_eq!(segments_of('a'), segments_of('A'));
+    }
+
+    // Pixels the text lights up when drawn white on a black surface
+    fn lit_pixels(typeface: &Typeface, text: &str) -> usize {
+        let surface = Surface::new(64, 32, PixelFormatEnum::RGB24).unwrap();
+        let mut canvas = surface.into_canvas().unwrap();
+        let texture_creator = canvas.texture_creator();
+        draw_text(&mut canvas, &texture_creator, typeface, text, (32, 16), Color::RGB(255, 255, 255)).unwrap();
+        let surface = canvas.into_surface();
+        surface.with_lock(|pixels| pixels.iter().filter(|&&byte| byte != 0).count())
+    }
+
+    #[test]
+    fn falls_back_to_segments_without_fonts() {
+        let typeface = Typeface::load(None, 20);
+        assert!(typeface.is_fallback());
+        assert_eq!(typeface.size_of("12"), Ok((30, 20)));
+
+        let one = lit_pixels(&typeface, "1");
+        assert!(one > 0);
+        assert!(lit_pixels(&typeface, "8") > one);
+        assert!(lit_pixels(&typeface, "88") > lit_pixels(&typeface, "8"));
+        assert_eq!(lit_pixels(&typeface, "?"), 0);
+    }
+}
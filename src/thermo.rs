@@ -0,0 +1,77 @@
+// Thermometers, paths of cells whose numbers have to go up from the bulb at the start of each one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Thermometers {
+    // Cells of every thermometer as (x, y), starting from the bulb
+    pub paths: Vec<Vec<(usize, usize)>>,
+}
+
+impl Thermometers {
+    // One thermometer per line, its cells as `x,y` separated by spaces starting from the bulb,
+    // blank lines and lines starting with `#` are ignored
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let parse_cell = |cell: &str| {
+            let invalid = || format!("invalid thermometer cell `{}`, expected `x,y`", cell);
+            let (x, y) = cell.split_once(',').ok_or_else(invalid)?;
+            Ok((x.trim().parse().map_err(|_| invalid())?, y.trim().parse().map_err(|_| invalid())?))
+        };
+
+        let paths = text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| line.split_whitespace().map(parse_cell).collect::<Result<Vec<_>, String>>())
+            .collect::<Result<Vec<_>, String>>()?;
+
+        Ok(Self { paths })
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for path in &self.paths {
+            let cells: Vec<String> = path.iter().map(|(x, y)| format!("{},{}", x, y)).collect();
+            text += &cells.join(" ");
+            text += "\n";
+        }
+        text
+    }
+}
+
+// Given the candidates of every cell of a thermometer from the bulb on, returns the range each cell
+// can still hold, every cell has to be above the smallest number possible before it and below the
+// largest one possible after it, `None` when some cell has nothing left in its range
+pub(crate) fn bounds(candidates: &[Vec<u32>]) -> Option<Vec<(u32, u32)>> {
+    let mut lowest = Vec::with_capacity(candidates.len());
+    let mut floor = 0;
+    for nums in candidates {
+        floor = *nums.iter().filter(|&&num| num > floor).min()?;
+        lowest.push(floor);
+    }
+
+    let mut highest = vec![0; candidates.len()];
+    let mut ceiling = u32::MAX;
+    for (i, nums) in candidates.iter().enumerate().rev() {
+        ceiling = *nums.iter().filter(|&&num| num < ceiling).max()?;
+        highest[i] = ceiling;
+    }
+
+    lowest.into_iter().zip(highest).map(|(low, high)| (low <= high).then_some((low, high))).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bounds_leave_room_along_the_thermometer() {
+        let all: Vec<u32> = (1..=9).collect();
+        assert_eq!(bounds(&[all.clone(), all.clone(), all.clone()]), Some(vec![(1, 7), (2, 8), (3, 9)]));
+
+        // A 5 in the bulb pushes everything after it up, a 2 at the end pulls the bulb down to a 1
+        assert_eq!(bounds(&[vec![5], all.clone(), all.clone()]), Some(vec![(5, 5), (6, 8), (7, 9)]));
+        assert_eq!(bounds(&[all.clone(), vec![2]]), Some(vec![(1, 1), (2, 2)]));
+        assert_eq!(bounds(&[vec![3, 4], vec![1, 2, 3, 4]]), Some(vec![(3, 3), (4, 4)]));
+
+        assert_eq!(bounds(&[vec![9], all.clone()]), None);
+        assert_eq!(bounds(&[vec![4, 6], vec![5], vec![3, 5]]), None);
+    }
+}
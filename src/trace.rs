@@ -0,0 +1,268 @@
+use crate::json::Json;
+use crate::sandwich::SandwichClues;
+use crate::solver::{Solver, Step};
+use crate::sudoku::{Deductions, Sudoku};
+use crate::thermo::Thermometers;
+
+// Everything needed to reproduce a solve: the puzzle, the seed it was solved with,
+// every step taken and where it ended up
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trace {
+    pub seed: u64,
+    // Whether the solver deduced what it could before each collapse
+    pub propagate: bool,
+    // Deductions used when propagating
+    pub deductions: Deductions,
+    pub random: bool,
+    pub puzzle: String,
+    // What the text of the puzzle leaves out, the variant rules and the candidates picked by hand
+    pub windoku: bool,
+    pub sandwich: Option<SandwichClues>,
+    pub thermometers: Option<Thermometers>,
+    // Empty cells whose candidates were locked, as (x, y, candidates)
+    pub locked: Vec<(usize, usize, Vec<u32>)>,
+    pub steps: Vec<Step>,
+    pub solution: String,
+}
+
+impl Trace {
+    // The solver is expected to have started from `puzzle`
+    pub fn record(puzzle: &Sudoku, solver: &Solver) -> Self {
+        Self {
+            seed: solver.seed(),
+            propagate: solver.propagates(),
+            deductions: solver.deductions(),
+            random: solver.is_random(),
+            puzzle: puzzle.to_string(),
+            windoku: puzzle.is_windoku(),
+            sandwich: puzzle.sandwich_clues().cloned(),
+            thermometers: puzzle.thermometers().cloned(),
+            locked: puzzle
+                .empty_cells()
+                .filter(|&(x, y)| puzzle.get_cell(x, y).candidates_locked())
+                .map(|(x, y)| (x, y, puzzle.get_cell(x, y).value().as_vec()))
+                .collect(),
+            steps: solver.history().to_vec(),
+            solution: solver.board().to_string(),
+        }
+    }
+
+    // The puzzle as it was recorded, with its variant rules and locked candidates
+    pub fn puzzle_board(&self) -> Result<Sudoku, String> {
+        let mut board = Sudoku::from_text(&self.puzzle);
+        board.set_windoku(self.windoku)?;
+        if let Some(clues) = &self.sandwich {
+            board.set_sandwich_clues(clues.clone())?;
+        }
+        if let Some(thermometers) = &self.thermometers {
+            board.set_thermometers(thermometers.clone())?;
+        }
+        for (x, y, nums) in &self.locked {
+            if *x >= board.dim() || *y >= board.dim() || !board.set_candidates(*x, *y, nums) {
+                return Err(format!("invalid locked candidates for cell ({}, {})", x, y));
+            }
+        }
+        Ok(board)
+    }
+
+    // Solves the puzzle again, checking that every step and the final board match the trace
+    pub fn replay(&self) -> Result<Sudoku, String> {
+        let mut solver = Solver::with_seed(self.puzzle_board()?, self.seed);
+        solver.set_propagate(self.propagate);
+        solver.set_deductions(self.deductions);
+        solver.set_random(self.random);
+
+        for (i, expected) in self.steps.iter().enumerate() {
+            let step = solver.step();
+            if step != *expected {
+                return Err(format!("step {} was {:?} but the trace has {:?}", i + 1, step, expected));
+            }
+        }
+
+        // With propagation the last deductions only happen on the step that finds the board complete
+        if solver.step() != Step::Complete {
+            return Err("replay did not end on a complete board".to_string());
+        }
+
+        let board = solver.board().clone();
+        if board.to_string() != self.solution {
+            let expected = Sudoku::from_text(&self.solution);
+            if expected.dim() != board.dim() {
+                return Err("replay ended on a different board than the trace".to_string());
+            }
+            let cells: Vec<String> = board.diff(&expected).iter().map(|(x, y)| format!("({}, {})", x, y)).collect();
+            return Err(format!("replay ended on a different board than the trace at {}", cells.join(", ")));
+        }
+
+        Ok(board)
+    }
+
+    pub fn to_json(&self) -> String {
+        let steps = self
+            .steps
+            .iter()
+            .filter_map(|step| match *step {
+                Step::Collapsed { x, y, digit } => Some(Json::Array(vec![
+                    Json::Number(x as f64),
+                    Json::Number(y as f64),
+                    Json::Number(digit as f64),
+                ])),
+                Step::Backtracked => Some(Json::String("backtrack".to_string())),
+                Step::Complete | Step::Unsolvable => None,
+            })
+            .collect();
+        let locked = self
+            .locked
+            .iter()
+            .map(|(x, y, nums)| {
+                let nums = nums.iter().map(|&num| Json::Number(num as f64)).collect();
+                Json::Array(vec![Json::Number(*x as f64), Json::Number(*y as f64), Json::Array(nums)])
+            })
+            .collect();
+        let text_or_null = |text: Option<String>| text.map_or(Json::Null, Json::String);
+
+        Json::Object(vec![
+            // Seeds don't fit in a double, so they're stored as text
+            ("seed".to_string(), Json::String(self.seed.to_string())),
+            ("propagate".to_string(), Json::Bool(self.propagate)),
+            ("deductions".to_string(), Json::String(self.deductions.to_list())),
+            ("random".to_string(), Json::Bool(self.random)),
+            ("puzzle".to_string(), Json::String(self.puzzle.clone())),
+            ("windoku".to_string(), Json::Bool(self.windoku)),
+            // In the same text as the `.sandwich` and `.thermo` files next to a puzzle
+            ("sandwich".to_string(), text_or_null(self.sandwich.as_ref().map(SandwichClues::to_text))),
+            ("thermometers".to_string(), text_or_null(self.thermometers.as_ref().map(Thermometers::to_text))),
+            ("locked".to_string(), Json::Array(locked)),
+            ("steps".to_string(), Json::Array(steps)),
+            ("solution".to_string(), Json::String(self.solution.clone())),
+        ])
+        .to_string()
+    }
+
+    pub fn from_json(text: &str) -> Result<Self, String> {
+        let json = Json::parse(text)?;
+        let field = |name: &str| json.get(name).ok_or(format!("missing `{}`", name));
+
+        let seed = field("seed")?
+            .as_str()
+            .and_then(|seed| seed.parse().ok())
+            .ok_or("`seed` must be a number in a string")?;
+        // Traces from before propagation was an option never propagated
+        let propagate = match json.get("propagate") {
+            Some(propagate) => propagate.as_bool().ok_or("`propagate` must be a boolean")?,
+            None => false,
+        };
+        // Before deductions could be picked every one of them was used
+        let deductions = match json.get("deductions") {
+            Some(deductions) => Deductions::parse(deductions.as_str().ok_or("`deductions` must be a string")?)?,
+            None => Deductions::all(),
+        };
+        let random = match json.get("random") {
+            Some(random) => random.as_bool().ok_or("`random` must be a boolean")?,
+            None => true,
+        };
+        let puzzle = field("puzzle")?.as_str().ok_or("`puzzle` must be a string")?.to_string();
+        let solution = field("solution")?.as_str().ok_or("`solution` must be a string")?.to_string();
+
+        // Traces from before the variants were recorded only had plain puzzles
+        let windoku = match json.get("windoku") {
+            Some(windoku) => windoku.as_bool().ok_or("`windoku` must be a boolean")?,
+            None => false,
+        };
+        let text = |name: &str| match json.get(name) {
+            None | Some(Json::Null) => Ok(None),
+            Some(text) => text.as_str().map(Some).ok_or(format!("`{}` must be a string or null", name)),
+        };
+        let sandwich = text("sandwich")?.map(SandwichClues::parse).transpose()?;
+        let thermometers = text("thermometers")?.map(Thermometers::parse).transpose()?;
+
+        let mut locked = vec![];
+        for cell in json.get("locked").map_or(Some(&[][..]), Json::as_array).ok_or("`locked` must be an array")? {
+            let invalid = || format!("invalid locked cell `{}`", cell);
+            let (x, y, nums) = match cell.as_array() {
+                Some([x, y, Json::Array(nums)]) => (x, y, nums),
+                _ => return Err(invalid()),
+            };
+            let nums: Option<Vec<u32>> = nums.iter().map(|num| num.as_u64().map(|num| num as u32)).collect();
+            match (x.as_u64(), y.as_u64(), nums) {
+                (Some(x), Some(y), Some(nums)) => locked.push((x as usize, y as usize, nums)),
+                _ => return Err(invalid()),
+            }
+        }
+
+        let mut steps = vec![];
+        for step in field("steps")?.as_array().ok_or("`steps` must be an array")? {
+            if step.as_str() == Some("backtrack") {
+                steps.push(Step::Backtracked);
+                continue;
+            }
+
+            // Every entry has to be a number, a step with anything else in it is malformed rather than shorter
+            let nums: Option<Vec<u64>> = step.as_array().and_then(|nums| nums.iter().map(Json::as_u64).collect());
+            match nums.as_deref() {
+                Some(&[x, y, digit]) => steps.push(Step::Collapsed { x: x as usize, y: y as usize, digit: digit as u32 }),
+                _ => return Err(format!("invalid step `{}`", step)),
+            }
+        }
+
+        Ok(Self { seed, propagate, deductions, random, puzzle, windoku, sandwich, thermometers, locked, steps, solution })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recorded_trace() -> Trace {
+        let puzzle = Sudoku::sample("sudoku2").unwrap();
+        let mut solver = Solver::with_seed(puzzle.clone(), 42);
+        assert!(solver.solve());
+        Trace::record(&puzzle, &solver)
+    }
+
+    #[test]
+    fn round_trips_through_json() {
+        let trace = recorded_trace();
+        let loaded = Trace::from_json(&trace.to_json()).unwrap();
+        assert_eq!(loaded, trace);
+
+        let board = loaded.replay().unwrap();
+        assert_eq!(board.to_string(), trace.solution);
+    }
+
+    #[test]
+    fn keeps_variants_and_locked_candidates() {
+        let mut thermo = Sudoku::from_text(include_str!("../samples/thermo1.txt"));
+        thermo.set_thermometers(Thermometers::parse(include_str!("../samples/thermo1.thermo")).unwrap()).unwrap();
+        let mut sandwich = Sudoku::from_text(include_str!("../samples/sandwich1.txt"));
+        sandwich.set_sandwich_clues(SandwichClues::parse(include_str!("../samples/sandwich1.sandwich")).unwrap()).unwrap();
+        let mut windoku = Sudoku::from_text(&".........\n".repeat(9));
+        windoku.set_windoku(true).unwrap();
+        let mut locked = Sudoku::sample("sudoku1").unwrap();
+        let answer = locked.solution().unwrap().certain_value(2, 0).unwrap();
+        locked.set_candidates(2, 0, &[answer, answer % 9 + 1]);
+
+        for puzzle in [thermo, sandwich, windoku, locked] {
+            let mut solver = Solver::with_seed(puzzle.clone(), 7);
+            assert!(solver.solve());
+            let trace = Trace::from_json(&Trace::record(&puzzle, &solver).to_json()).unwrap();
+
+            let board = trace.puzzle_board().unwrap();
+            assert_eq!(board.is_windoku(), puzzle.is_windoku());
+            assert_eq!(board.sandwich_clues(), puzzle.sandwich_clues());
+            assert_eq!(board.thermometers(), puzzle.thermometers());
+            assert_eq!(board.get_cell(2, 0).candidates_locked(), puzzle.get_cell(2, 0).candidates_locked());
+            assert_eq!(trace.replay().unwrap().to_string(), solver.board().to_string());
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_steps() {
+        let json = recorded_trace().to_json();
+        let steps_start = json.find("\"steps\":[").unwrap() + "\"steps\":[".len();
+        for step in ["[1,-1,2,3]", "[1,\"a\",3]", "[1,2]", "7"] {
+            let broken = format!("{}{},{}", &json[..steps_start], step, &json[steps_start..]);
+            assert!(Trace::from_json(&broken).is_err(), "{} was accepted", step);
+        }
+    }
+}